@@ -7,8 +7,33 @@ use scale_info::TypeInfo;
 #[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy)]
 pub struct Participate {
     pub amount_bought: u128,
+    /// Paid amount in `currency`.
     pub amount_paid_gear: u128,
-    pub participated_datetime: u64 
+    /// Currency the participant pays with, refunds are paid in it too.
+    pub currency: Currency,
+    pub amount_claimed: u128,
+    pub participated_datetime: u64,
+    /// Paid amount to give back: committed above the settled allocation in overflow sale,
+    /// or cut with the purchase after a stake drop.
+    pub overflow_refund_gear: u128,
+    pub refunded: bool,
+}
+
+/// Asset accepted as payment for sold tokens.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Currency {
+    #[default]
+    Gear,
+    Token(ActorId),
+}
+
+/// Vesting schedule for bought allocations.
+/// Time values are in milliseconds and counted from the sale's end (TGE).
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy)]
+pub struct VestingParameters {
+    pub tge_unlock_percent: u8,
+    pub cliff_duration: u64,
+    pub vesting_duration: u64,
 }
 
 #[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy)]
@@ -17,8 +42,148 @@ pub struct SaleParameters {
     pub owner: ActorId,
     pub staking: ActorId,
     pub tokens_to_sell: u128,
+    /// Price of a whole token in the base currency.
     pub token_price_in_gear: u128,
     pub registration_fee_gear: u128,
+    /// Minimal amount to raise in the base currency, zero disables the soft cap.
+    pub soft_cap: u128,
+    /// Lets participants commit any amount, tokens are split pro rata to the payments after the sale.
+    /// Tiers, vouchers and pushed allocations are rejected in this mode.
+    pub overflow: bool,
+    pub vesting: Option<VestingParameters>,
+    /// Merkle root of the addresses allowed to register, `None` keeps registration open.
+    /// Leaves are `sha256((ActorId, Option<u128>).encode())`, pairs are hashed sorted.
+    pub whitelist_root: Option<[u8; 32]>,
+    /// Ed25519 public key signing allocation vouchers, `None` disables vouchers.
+    pub allocator_key: Option<[u8; 32]>,
+    /// Fungible token the sale is priced in, the base currency is GEAR if it's `None`.
+    /// More currencies can be accepted with `AddPaymentCurrency`. Registration fees stay in GEAR.
+    pub payment_token: Option<ActorId>,
+}
+
+/// Max allocation size granted off-chain by the allocator key.
+#[derive(Debug, Decode, Encode, TypeInfo, Clone, Copy)]
+pub struct AllocationVoucher {
+    /// Address of the sale program the voucher is issued for.
+    pub sale_id: ActorId,
+    pub user: ActorId,
+    pub max_allocation: u128,
+    pub expiry: u64,
+    /// Orders the vouchers issued to the same user, only the latest one is accepted.
+    pub issued_at: u64,
+}
+
+/// Voucher with the allocator's ed25519 signature of its SCALE encoding.
+#[derive(Debug, Decode, Encode, TypeInfo, Clone, Copy)]
+pub struct SignedVoucher {
+    pub voucher: AllocationVoucher,
+    pub signature: [u8; 64],
+}
+
+/// How uploaded allocation sizes combine with the ones already set.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationMode {
+    /// Replaces the current value, so resending a corrected file is safe.
+    #[default]
+    Set,
+    Increase,
+    Decrease,
+}
+
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy)]
+pub struct AllocationChange {
+    pub user: ActorId,
+    pub previous: u128,
+    pub new: u128,
+}
+
+/// Part of an allocation upload split over several messages.
+/// Every `(upload_id, index)` pair is applied once, so a chunk can be safely resent.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone)]
+pub struct AllocationChunk {
+    pub upload_id: u32,
+    pub index: u32,
+    pub mode: AllocationMode,
+    pub allocations: BTreeMap<ActorId, u128>,
+}
+
+/// Outcome of an allocation upload: entries of registered users are applied,
+/// the rest are rejected without failing the whole batch.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone)]
+pub struct AllocationUploadSummary {
+    /// `(upload_id, index)` of the chunk, if the upload is chunked.
+    pub chunk: Option<(u32, u32)>,
+    pub applied: Vec<AllocationChange>,
+    pub rejected: Vec<ActorId>,
+}
+
+/// Proof of the caller's leaf in the whitelist Merkle tree.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone)]
+pub struct WhitelistProof {
+    pub proof: Vec<[u8; 32]>,
+    /// Max allocation size included into the leaf, if the whitelist assigns one.
+    pub allocation: Option<u128>,
+}
+
+/// Cut of the earnings paid out before the sale owner gets the rest.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, PartialEq, Eq)]
+pub struct PlatformFee {
+    /// Launchpad fee in basis points.
+    pub fee_bps: u16,
+    pub treasury: ActorId,
+    /// Extra recipients and their shares in basis points, taken on top of the launchpad fee.
+    pub recipients: Vec<(ActorId, u16)>,
+}
+
+/// How the Dutch auction price falls over the sale round.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PriceDecay {
+    #[default]
+    Linear,
+    /// Falls in this many equal steps, reaching the floor price at the end of the round.
+    Steps(u32),
+}
+
+/// Price falling from `start_price` to `floor_price` over the sale round.
+/// Both are prices of a whole token in the base currency, other currencies keep their ratio to it.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DutchAuction {
+    pub start_price: u128,
+    pub floor_price: u128,
+    pub decay: PriceDecay,
+    /// Everyone pays the price of the last purchase, the difference is refunded after `Settle`.
+    pub uniform_clearing: bool,
+}
+
+/// First-come-first-served round held after the sale round.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy)]
+pub struct FcfsParameters {
+    pub start_datetime: u64,
+    pub end_datetime: u64,
+    /// Tokens a single wallet can buy during the round.
+    pub max_allocation: u128,
+    /// Lets users that are not registered participate too.
+    pub open_to_all: bool,
+}
+
+/// Registered users with at least `min_stake` PLPD staked share `tokens_to_sell` by `weight`.
+#[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy)]
+pub struct Tier {
+    pub min_stake: u128,
+    pub weight: u128,
+}
+
+/// Narrow permissions the admin can hand out. The admin itself holds all of them.
+#[derive(Debug, Decode, Encode, TypeInfo, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Sets max allocation sizes.
+    Allocator,
+    /// Sets the round times.
+    Scheduler,
+    /// Withdraws registration fees.
+    Treasurer,
+    /// Pauses and unpauses the sale.
+    Pauser,
 }
 
 #[derive(Debug, Decode, Encode, TypeInfo)]
@@ -28,73 +193,251 @@ pub enum SaleAction {
     SetSaleToken(ActorId),
     SetRegistrationTime(u64, u64),
     SetSaleTime(u64, u64),
-    SetMaxAllocationSizes(BTreeMap<ActorId, u128>),
+    SetFcfsRound(FcfsParameters),
+    SetTiers(Vec<Tier>),
+    /// Accepts the currency at the given price of a whole token, or updates its price.
+    AddPaymentCurrency(Currency, u128),
+    SetPlatformFee(PlatformFee),
+    SetDutchAuction(DutchAuction),
+    SetMaxAllocationSizes(AllocationMode, BTreeMap<ActorId, u128>),
+    UploadAllocationChunk(AllocationChunk),
 
     GetAllocationSizeOf(ActorId),
     GetParticipationOf(ActorId),
+    GetClaimableOf(ActorId),
     GetSaleToken,
     GetTotalSold,
     GetTotalRaised,
 
     RegisterOnSale,
+    RegisterOnSaleWithProof(WhitelistProof),
+    Unregister,
+    /// Re-reads a registered user's stake and cuts their allocation, and any purchase above it,
+    /// if the stake went down. Stakes aren't locked by the sale, so a keeper has to run this
+    /// for buyers until the sale round ends, a purchase is only cut when it's checked.
+    CheckStake(ActorId),
     Participate,
+    ParticipateWithVoucher(SignedVoucher),
+    /// Pays the amount of the given fungible token, optionally with an allocation voucher.
+    ParticipateWithTokens(ActorId, u128, Option<SignedVoucher>),
 
     DepositTokens,
 
     WithdrawAllocation,
+    ClaimAllocation(u128),
     WithdrawLeftover,
     WithdrawEarnings,
     WithdrawRegistrationFees,
+    ClaimRefund,
+    Settle,
 
     RemoveRegistered(ActorId),
-    CloseGate
+    CloseGate,
+    CancelSale,
+    Pause,
+    /// Whether to extend the round active at the pause by the pause length.
+    Unpause(bool),
+
+    ProposeAdmin(ActorId),
+    AcceptAdmin,
+    ProposeOwner(ActorId),
+    AcceptOwner,
+
+    GrantRole(Role, ActorId),
+    RevokeRole(Role, ActorId)
 }
 
 #[derive(Debug, Encode, Decode, TypeInfo)]
 pub enum SaleEvent {
     SaleCreated(SaleParameters),
     UserRegistered(ActorId),
+    /// Unregistered user and the registration fee returned to them.
+    UserUnregistered(ActorId, u128),
+    /// User, current stake, max allocation after the check.
+    StakeChecked(ActorId, u128, u128),
     RegistrationGEARRefunded(ActorId, u128),
+    /// Buyer, tokens bought, GEAR charged, GEAR refunded as change.
+    TokensPurchased(ActorId, u128, u128, u128),
 
     AllocationWithdrawn(ActorId, u128),
+    /// Participant, claimed amount, amount left unclaimed.
+    AllocationClaimed(ActorId, u128, u128),
     RegistrationFeeWithdrawn(u128),
-    EarningsWithdrawn(u128),
+    /// Earnings left for the sale owner after the fee split.
+    EarningsWithdrawn(Vec<(Currency, u128)>),
+    /// Recipient, currency and amount of a single fee split, sent to the recipient.
+    EarningsSplit(ActorId, Currency, u128),
+    RefundClaimed(ActorId, u128),
+    GearCommitted(ActorId, u128),
+    /// Total tokens sold and total GEAR raised after settlement.
+    SaleSettled(u128, u128),
 
     RegistrationTimeSet(u64),
     SaleTimeSet(u64),
+    FcfsRoundSet(u64),
+    TiersSet(u64),
+    PaymentCurrencyAdded(Currency, u128),
+    PlatformFeeSet(PlatformFee),
+    DutchAuctionSet(DutchAuction),
     SaleTokenSet(ActorId),
-    MaxAllocationSizeSet((ActorId, u128)),
+    MaxAllocationSizesSet(AllocationUploadSummary),
 
     RegisteredRemoved(ActorId),
     GateClosed(u64),
+    Paused(u64),
+    /// Unpause time and how long the rounds were extended by.
+    Unpaused(u64, u64),
+    AdminProposed(ActorId),
+    AdminChanged(ActorId),
+    OwnerProposed(ActorId),
+    OwnerChanged(ActorId),
+    RoleGranted(Role, ActorId),
+    RoleRevoked(Role, ActorId),
+
+    SaleConfigured(u64),
+    RegistrationStarted(u64),
+    RegistrationEnded(u64),
+    SaleStarted(u64),
+    SaleEnded(u64),
+    SaleFinalized(u64),
+    SaleCancelled(u64),
+    SaleFailed(u64),
 
     SaleToken(ActorId),
     AllocationSize(u128),
     Participation(Participate),
+    Claimable(u128),
     TotalSold(u128),
-    TotalRaised(u128),
+    TotalRaised(Vec<(Currency, u128)>),
+
+    Error(SaleError),
+}
+
+#[derive(Debug, Encode, Decode, TypeInfo, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SalePhase {
+    /// Sale is not created or its rounds are not scheduled yet.
+    #[default]
+    Draft,
+    /// Everything is set, registration hasn't started yet.
+    Configured,
+    Registration,
+    /// Registration is over, the sale round hasn't started yet.
+    RegistrationEnded,
+    /// From the start of the sale round till the end of the last round.
+    Sale,
+    /// Sale is over, but its results still have to be settled.
+    Ended,
+    /// Results are final, bought tokens can be claimed.
+    Finalized,
+    Cancelled,
+    /// Sale hasn't reached its soft cap, participants are refunded.
+    Failed,
+}
+
+#[derive(Debug, Encode, Decode, TypeInfo, Clone, Copy, PartialEq, Eq)]
+pub enum SaleError {
+    NotAdmin,
+    NotSaleOwner,
+    GateClosed,
+    Paused,
+    AlreadyPaused,
+    NotPaused,
+    InvalidAdmin,
+    NotPendingAdmin,
+    NotPendingOwner,
+    MissingRole(Role),
+    WrongPhase(SalePhase),
+    InvalidPhaseTransition,
+
+    SaleNotCreated,
+    SaleAlreadyCreated,
+    InvalidSaleOwner,
+    ZeroTokensToSell,
+    InvalidVesting,
+    TokenNotSet,
+    TokensNotDeposited,
+    TokensAlreadyDeposited,
+    RegistrationTimeNotSet,
+    InvalidRegistrationTime,
+    InvalidSaleTime,
+    InvalidFcfsRound,
+    InvalidTiers,
+    InvalidPlatformFee,
+    InvalidDutchAuction,
+    TiersLocked,
+    AllocationsDerivedFromTiers,
+    /// Overflow sales split tokens by payment alone, allocations don't apply to them.
+    AllocationsUnusedInOverflow,
+    ChunkAlreadyUploaded,
+
+    InvalidRegistrationFee,
+    WhitelistNotSet,
+    NotWhitelisted,
+    AllocatorKeyNotSet,
+    InvalidVoucher,
+    VoucherExpired,
+    VoucherSuperseded,
+    NoStake,
+    StakeBelowLowestTier,
+    AlreadyRegistered,
+    NotRegistered,
+
+    SaleRoundNotActive,
+    ZeroAmount,
+    WrongPaymentMethod,
+    CurrencyNotAccepted,
+    CurrencyMismatch,
+    InvalidPrice,
+    AllocationExceeded,
+    NotEnoughTokens,
+
+    /// Only overflow sales and uniformly cleared auctions are settled.
+    NothingToSettle,
+
+    NotParticipant,
+    NothingToWithdraw,
+    ClaimExceedsClaimable,
+    EarningsAlreadyWithdrawn,
+    LeftoverAlreadyWithdrawn,
+    NothingToRefund,
+    AlreadyRefunded,
+    /// The token contract rejected the transfer, nothing was recorded as paid out.
+    TransferFailed,
 }
 
 #[derive(Debug, Encode, Decode, TypeInfo)]
 pub enum SaleState {
     GetAllocationSizeOf(ActorId),
+    GetTierOf(ActorId),
     GetParticipationOf(ActorId),
+    GetClaimableOf(ActorId),
     GetSaleRoundTime,
     GetRegistrationRoundTime,
+    GetFcfsRoundTime,
     GetSaleOwner,
+    GetPhase,
+    GetRolesOf(ActorId),
     GetSaleToken,
     GetTotalSold,
     GetTotalRaised,
+    GetCurrentPrice(Currency),
 }
 
 #[derive(Debug, Encode, Decode, TypeInfo)]
 pub enum SaleReply {
     SaleToken(ActorId),
     SaleOwner(ActorId),
+    Phase(SalePhase),
+    Roles(Vec<Role>),
     SaleRoundTime(u64, u64),
     RegistrationRoundTime(u64, u64),
+    FcfsRoundTime(u64, u64),
     AllocationSize(u128),
+    Tier(Option<u32>),
     Participation(Participate),
+    Claimable(u128),
     TotalSold(u128),
-    TotalRaised(u128),
+    TotalRaised(Vec<(Currency, u128)>),
+    /// Current price of a whole token, `None` if the currency isn't accepted.
+    CurrentPrice(Option<u128>),
 }
\ No newline at end of file
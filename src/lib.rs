@@ -36,66 +36,146 @@ async unsafe fn main() {
     let action: SaleAction = msg::load()
         .expect("Polkapad Sale: unable to decode configuration");
 
-    match action {
+    if let Err(error) = sale.sync_phase() {
+        msg::reply(SaleEvent::Error(error), msg::value()).unwrap();
+        return;
+    }
+
+    let result = match action {
         SaleAction::CreateSale(parameters) => {
-            sale.set_sale_parameters(parameters);
+            sale.set_sale_parameters(parameters)
         },
         SaleAction::SetSaleToken(token_address) => {
-            sale.set_sale_token(token_address);
+            sale.set_sale_token(token_address)
         },
         SaleAction::SetRegistrationTime(start_datetime, end_datetime) => {
-            sale.set_registration_time(start_datetime, end_datetime);
+            sale.set_registration_time(start_datetime, end_datetime)
         },
         SaleAction::SetSaleTime(start_datetime, end_datetime) => {
-            sale.set_sale_time(start_datetime, end_datetime);
+            sale.set_sale_time(start_datetime, end_datetime)
+        },
+        SaleAction::SetFcfsRound(parameters) => {
+            sale.set_fcfs_round(parameters)
+        },
+        SaleAction::SetTiers(tiers) => {
+            sale.set_tiers(tiers)
+        },
+        SaleAction::AddPaymentCurrency(currency, price) => {
+            sale.add_payment_currency(currency, price)
+        },
+        SaleAction::SetPlatformFee(fee) => {
+            sale.set_platform_fee(fee)
+        },
+        SaleAction::SetDutchAuction(auction) => {
+            sale.set_dutch_auction(auction)
+        },
+        SaleAction::SetMaxAllocationSizes(mode, users) => {
+            sale.set_allocation_sizes(mode, users)
         },
-        SaleAction::SetMaxAllocationSizes(users) => {
-            sale.set_allocation_sizes(users);
+        SaleAction::UploadAllocationChunk(chunk) => {
+            sale.upload_allocation_chunk(chunk)
         },
         SaleAction::DepositTokens => {
-            sale.deposit_tokens().await;
+            sale.deposit_tokens().await
         },
         SaleAction::RemoveRegistered(who) => {
-            sale.remove_registered(who);
+            sale.remove_registered(who)
+        },
+        SaleAction::Unregister => {
+            sale.unregister()
         },
         SaleAction::CloseGate => {
-            sale.close_gate();
+            sale.close_gate()
+        },
+        SaleAction::CancelSale => {
+            sale.cancel()
+        },
+        SaleAction::Pause => {
+            sale.pause()
+        },
+        SaleAction::Unpause(extend_round) => {
+            sale.unpause(extend_round)
+        },
+        SaleAction::ProposeAdmin(admin) => {
+            sale.propose_admin(admin)
+        },
+        SaleAction::AcceptAdmin => {
+            sale.accept_admin()
+        },
+        SaleAction::ProposeOwner(owner) => {
+            sale.propose_owner(owner)
+        },
+        SaleAction::AcceptOwner => {
+            sale.accept_owner()
+        },
+        SaleAction::GrantRole(role, who) => {
+            sale.grant_role(role, who)
+        },
+        SaleAction::RevokeRole(role, who) => {
+            sale.revoke_role(role, who)
         },
         SaleAction::RegisterOnSale => {
-            sale.register().await;
+            sale.register(None).await
+        },
+        SaleAction::RegisterOnSaleWithProof(proof) => {
+            sale.register(Some(proof)).await
+        },
+        SaleAction::CheckStake(who) => {
+            sale.check_stake(who).await
         },
         SaleAction::Participate => {
-            sale.participate().await;
+            sale.participate(None, None).await
+        },
+        SaleAction::ParticipateWithVoucher(voucher) => {
+            sale.participate(Some(voucher), None).await
+        },
+        SaleAction::ParticipateWithTokens(token, amount, voucher) => {
+            sale.participate(voucher, Some((token, amount))).await
         },
         SaleAction::WithdrawAllocation => {
-            sale.withdraw_allocation().await;
+            sale.withdraw_allocation().await
+        },
+        SaleAction::ClaimAllocation(amount) => {
+            sale.claim_allocation(amount).await
         },
         SaleAction::WithdrawEarnings => {
-            sale.withdraw_earnings();
+            sale.withdraw_earnings().await
         },
         SaleAction::WithdrawLeftover => {
-            sale.withdraw_leftover().await;
+            sale.withdraw_leftover().await
         },
         SaleAction::WithdrawRegistrationFees => {
-            sale.withdraw_registration_fees();
+            sale.withdraw_registration_fees()
+        },
+        SaleAction::ClaimRefund => {
+            sale.claim_refund().await
+        },
+        SaleAction::Settle => {
+            sale.settle().await
         },
         SaleAction::GetSaleToken => {
-            sale.get_sale_token();
+            sale.get_sale_token()
         },
         SaleAction::GetTotalRaised => {
-            sale.get_total_raised();
+            sale.get_total_raised()
         },
         SaleAction::GetTotalSold => {
-            sale.get_total_sold();
+            sale.get_total_sold()
         },
         SaleAction::GetAllocationSizeOf(participiant) => {
-            sale.get_allocation_size_of(participiant);
+            sale.get_allocation_size_of(participiant)
         },
         SaleAction::GetParticipationOf(participiant) => {
-            sale.get_participation_of(participiant);
+            sale.get_participation_of(participiant)
         },
-    }
+        SaleAction::GetClaimableOf(participiant) => {
+            sale.get_claimable_of(participiant)
+        },
+    };
 
+    if let Err(error) = result {
+        msg::reply(SaleEvent::Error(error), msg::value()).unwrap();
+    }
 }
 
 #[no_mangle]
@@ -105,21 +185,33 @@ pub unsafe extern "C" fn meta_state() -> *mut [i32; 2] {
 
     let encoded = match query {
         SaleState::GetAllocationSizeOf(who) => 
-            SaleReply::AllocationSize(*sale.registration.users.get(&who).unwrap_or(&0)),
+            SaleReply::AllocationSize(sale.max_allocation_of(&who)),
+        SaleState::GetTierOf(who) => 
+            SaleReply::Tier(sale.registration.user_tiers.get(&who).copied()),
         SaleState::GetParticipationOf(who) => 
             SaleReply::Participation(*sale.sale.participants.get(&who).unwrap_or(&Participate { ..Default::default() })),
+        SaleState::GetClaimableOf(who) => 
+            SaleReply::Claimable(sale.claimable_of(&who)),
         SaleState::GetSaleRoundTime => 
             SaleReply::SaleRoundTime(sale.registration.start_datetime, sale.registration.end_datetime),
         SaleState::GetRegistrationRoundTime => 
             SaleReply::RegistrationRoundTime(sale.sale.start_datetime, sale.sale.end_datetime),
+        SaleState::GetFcfsRoundTime => 
+            SaleReply::FcfsRoundTime(sale.fcfs.start_datetime, sale.fcfs.end_datetime),
         SaleState::GetSaleToken => 
             SaleReply::SaleToken(sale.token),
         SaleState::GetSaleOwner => 
             SaleReply::SaleOwner(sale.owner),
+        SaleState::GetPhase => 
+            SaleReply::Phase(sale.phase()),
+        SaleState::GetRolesOf(who) => 
+            SaleReply::Roles(sale.roles.iter().filter(|(_, holder)| *holder == who).map(|(role, _)| *role).collect()),
         SaleState::GetTotalSold => 
             SaleReply::TotalSold(sale.tokens_sold),
         SaleState::GetTotalRaised => 
-            SaleReply::TotalSold(sale.tokens_raised),
+            SaleReply::TotalRaised(sale.raised_by_currency()),
+        SaleState::GetCurrentPrice(currency) => 
+            SaleReply::CurrentPrice(sale.prices.contains_key(&currency).then(|| sale.price_of(currency))),
     }
     .encode();
     gstd::util::to_leak_ptr(encoded)
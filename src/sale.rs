@@ -1,79 +1,130 @@
 use gstd::{prelude::*, exec, msg, ActorId};
 
-use sale_io::{SaleEvent, SaleParameters, Participate};
+use sale_io::{SaleEvent, SaleError, SaleParameters, SalePhase, Participate, VestingParameters, FcfsParameters, Tier, Role, WhitelistProof, SignedVoucher, AllocationChunk, AllocationUploadSummary, AllocationMode, AllocationChange, Currency, PlatformFee, DutchAuction, PriceDecay};
 use ft_io::{FTAction, FTEvent};
 use staking_io::{StakingEvent, StakingAction};
 
-use crate::require;
+use crate::{require, utils::{mul_div, mul_div_ceil, sha256, verify_merkle_proof, verify_ed25519}};
 
 const ZERO_ID: ActorId = ActorId::new([0u8; 32]);
 const ZERO_MAX_ALLOCATION_SIZE: u128 = 0;
+const BPS_DENOMINATOR: u128 = 10_000;
 
 #[derive(Debug, Default)]
 pub struct RegistrationRound {
     pub start_datetime: u64,
     pub end_datetime: u64,
-    pub users: BTreeMap<ActorId, u128>
+    pub users: BTreeMap<ActorId, u128>,
+    pub user_tiers: BTreeMap<ActorId, u32>,
+    /// Stake snapshots taken on registration, lowered by `sync_stake`.
+    pub stakes: BTreeMap<ActorId, u128>,
+    pub total_weight: u128,
+    /// Registrants who got their fee back after the sale was cancelled.
+    pub refunded_fees: BTreeSet<ActorId>,
+    /// Issue time of the latest voucher recorded for each user. Replaying it doesn't
+    /// undo a later `sync_stake` cut, older ones are rejected.
+    pub vouchers: BTreeMap<ActorId, u64>,
+    /// `(upload_id, index)` of the allocation chunks applied so far.
+    pub uploaded_chunks: BTreeSet<(u32, u32)>
 }
 
 #[derive(Debug, Default)]
 pub struct SaleRound {
     pub start_datetime: u64,
     pub end_datetime: u64,
+    /// Total tier weight taken when the sale round starts, so a stake drop or removal
+    /// only shrinks the share of the user concerned.
+    pub total_weight: u128,
     pub participants: BTreeMap<ActorId, Participate>
 }
 
+#[derive(Debug, Default)]
+pub struct FcfsRound {
+    pub start_datetime: u64,
+    pub end_datetime: u64,
+    pub max_allocation: u128,
+    pub open_to_all: bool,
+    pub purchases: BTreeMap<ActorId, u128>
+}
+
 #[derive(Debug, Default)]
 pub struct Sale {
     pub admin: ActorId,
     pub owner: ActorId,
+    pub pending_admin: Option<ActorId>,
+    pub pending_owner: Option<ActorId>,
+    pub roles: BTreeSet<(Role, ActorId)>,
     pub token: ActorId,
     pub staking: ActorId,
     pub registration: RegistrationRound,
     pub sale: SaleRound,
+    pub fcfs: FcfsRound,
+    pub tiers: Vec<Tier>,
+    /// Phase as of the last `sync_phase`, moved only through `transition`.
+    pub phase: SalePhase,
 
     pub tokens_to_sell: u128,
     pub tokens_sold: u128,
+    /// Everything raised converted to the base currency, compared with `soft_cap`.
     pub tokens_raised: u128,
-    pub token_price_in_gear: u128,
+    pub raised: BTreeMap<Currency, u128>,
+    pub base_currency: Currency,
+    /// Prices of a whole token in every accepted currency.
+    pub prices: BTreeMap<Currency, u128>,
     pub tokens_deposited: bool,
 
     pub registration_fee_gear: u128,
     pub registration_fees: u128,
 
-    pub earnings_withdrawn: bool,
+    pub soft_cap: u128,
+    pub overflow: bool,
+    pub settled: bool,
+    pub vesting: Option<VestingParameters>,
+    pub whitelist_root: Option<[u8; 32]>,
+    pub allocator_key: Option<[u8; 32]>,
+    pub platform_fee: Option<PlatformFee>,
+    pub auction: Option<DutchAuction>,
+    /// Base currency price of the last auction purchase, paid by everyone with uniform clearing.
+    pub clearing_price: u128,
+
+    /// `(currency, payout slot)` of the earnings shares paid out, see `earnings_payouts`.
+    pub earnings_paid: BTreeSet<(Currency, u32)>,
     pub leftover_withdrawn: bool,
     pub is_created: bool,
 
-    pub gate_closed: bool
+    pub gate_closed: bool,
+    /// Start of the current pause, if the sale is paused.
+    pub paused_at: Option<u64>
 }
 
 impl Sale {
-    pub async fn register(&mut self) {
-        let reply: StakingEvent = msg::send_for_reply_as::<_, StakingEvent>(
-            self.staking,
-            StakingAction::StakeOf(msg::source()),
-            0,
-        )
-        .unwrap()
-        .await
-        .expect("Function call error");
+    pub async fn register(&mut self, whitelist_proof: Option<WhitelistProof>) -> Result<(), SaleError> {
+        self.only_if_not_paused()?;
 
-        let balance = if let StakingEvent::Staked(balance) = reply { balance } else { panic!("Error while parsing event") };
+        let allocation = self.check_whitelist(whitelist_proof)?;
 
-        require!(balance > 0, "You need to stake PLPD to participate this sale");
+        let balance = self.stake_of(msg::source()).await;
 
-        require!(self.registration_fee_gear == msg::value(), "Registration deposit doesn't match");
-        require!(exec::block_timestamp() >= self.registration.start_datetime &&
-                 exec::block_timestamp() <= self.registration.end_datetime,
-            "Registration round is over"
-        );
+        require!(balance > 0, SaleError::NoStake);
+
+        require!(self.registration_fee_gear == msg::value(), SaleError::InvalidRegistrationFee);
+        self.only_in_phase(&[SalePhase::Registration])?;
         require!(
             self.registration.users.get(&msg::source()).is_none(),
-            "User already registered"
+            SaleError::AlreadyRegistered
         );
 
-        self.registration.users.insert(msg::source(), ZERO_MAX_ALLOCATION_SIZE);
+        if !self.tiers.is_empty() {
+            let tier = self.tiers.iter().rposition(|tier| balance >= tier.min_stake);
+            require!(tier.is_some(), SaleError::StakeBelowLowestTier);
+
+            let tier = tier.unwrap();
+            self.registration.user_tiers.insert(msg::source(), tier as u32);
+            self.registration.total_weight = self.registration.total_weight.saturating_add(self.tiers[tier].weight);
+        }
+
+        self.registration.users.insert(msg::source(), allocation.unwrap_or(ZERO_MAX_ALLOCATION_SIZE));
+        self.registration.stakes.insert(msg::source(), balance);
         self.registration_fees = self.registration_fees.saturating_add(msg::value());
 
         msg::send_for_reply(
@@ -82,91 +133,435 @@ impl Sale {
             .unwrap()
             .await
             .expect("Sending message error");
+
+        Ok(())
     }
 
-    pub async fn participate(&mut self) {
-        require!(exec::block_timestamp() >= self.sale.start_datetime &&
-                 exec::block_timestamp() <= self.sale.end_datetime,
-            "Sale round is over"
+    /// Verifies the caller against the whitelist, if there is one.
+    /// Returns the allocation proven along with the address.
+    fn check_whitelist(&self, whitelist_proof: Option<WhitelistProof>) -> Result<Option<u128>, SaleError> {
+        let root = match self.whitelist_root {
+            Some(root) => root,
+            None => {
+                require!(whitelist_proof.is_none(), SaleError::WhitelistNotSet);
+                return Ok(None);
+            },
+        };
+
+        let WhitelistProof { proof, allocation } = whitelist_proof.ok_or(SaleError::NotWhitelisted)?;
+
+        let leaf = sha256(&(msg::source(), allocation).encode());
+        require!(verify_merkle_proof(&root, leaf, &proof), SaleError::NotWhitelisted);
+        require!(allocation.is_none() || self.tiers.is_empty(), SaleError::AllocationsDerivedFromTiers);
+        require!(allocation.is_none() || !self.overflow, SaleError::AllocationsUnusedInOverflow);
+
+        Ok(allocation)
+    }
+
+    pub async fn participate(&mut self, voucher: Option<SignedVoucher>, token_payment: Option<(ActorId, u128)>) -> Result<(), SaleError> {
+        self.only_if_not_paused()?;
+        self.only_in_phase(&[SalePhase::Sale])?;
+
+        let is_sale_round = exec::block_timestamp() >= self.sale.start_datetime &&
+                            exec::block_timestamp() <= self.sale.end_datetime;
+        let is_fcfs_round = self.is_fcfs_round();
+        require!(is_sale_round || is_fcfs_round, SaleError::SaleRoundNotActive);
+
+        let is_registered = self.registration.users.get(&msg::source()).is_some();
+        require!(
+            is_registered || (is_fcfs_round && self.fcfs.open_to_all),
+            SaleError::NotRegistered
         );
 
+        if let Some(voucher) = voucher {
+            require!(is_registered, SaleError::NotRegistered);
+            self.record_voucher(voucher)?;
+        }
+
+        if is_registered && is_sale_round {
+            self.sync_stake(msg::source()).await;
+        }
+
+        let currency = token_payment.map_or(Currency::Gear, |(token, _)| Currency::Token(token));
+        require!(self.prices.contains_key(&currency), SaleError::CurrencyNotAccepted);
+        if let Some(participation) = self.sale.participants.get(&msg::source()) {
+            require!(participation.currency == currency, SaleError::CurrencyMismatch);
+        }
+
+        let payment = self.receive_payment(token_payment).await?;
+
+        let purchase = if self.overflow {
+            self.commit(currency, payment)
+        } else {
+            let token_unit = self.token_unit().await;
+            self.buy(token_unit, is_fcfs_round, currency, payment)
+        };
+
+        let (event, change, is_first_purchase) = match purchase {
+            Ok(purchase) => purchase,
+            Err(error) => {
+                if let Currency::Token(token) = currency {
+                    transfer_tokens(&token, &exec::program_id(), &msg::source(), payment).await;
+                }
+                return Err(error);
+            },
+        };
+
+        self.reply_with_payment(currency, event, change).await;
+
+        if !is_first_purchase || !is_registered {
+            return Ok(());
+        }
+
+        self.registration_fees = self.registration_fees.saturating_sub(self.registration_fee_gear);
+
+        msg::send_for_reply(
+            msg::source(),
+            SaleEvent::RegistrationGEARRefunded(msg::source(), self.registration_fee_gear), 
+            self.registration_fee_gear)
+            .unwrap()
+            .await
+            .expect("Sending message error");
+
+        Ok(())
+    }
+
+    /// Sets the caller's max allocation size from a voucher signed by the allocator key.
+    fn record_voucher(&mut self, signed_voucher: SignedVoucher) -> Result<(), SaleError> {
+        let SignedVoucher { voucher, signature } = signed_voucher;
+
+        let allocator_key = self.allocator_key.ok_or(SaleError::AllocatorKeyNotSet)?;
+        require!(self.tiers.is_empty(), SaleError::AllocationsDerivedFromTiers);
+        require!(!self.overflow, SaleError::AllocationsUnusedInOverflow);
         require!(
-            self.registration.users.get(&msg::source()).is_some(),
-            "User must be registered"
+            voucher.sale_id == exec::program_id() && voucher.user == msg::source(),
+            SaleError::InvalidVoucher
         );
+        require!(voucher.expiry >= exec::block_timestamp(), SaleError::VoucherExpired);
         require!(
-            self.sale.participants.get(&msg::source()).is_none(),
-            "User already participated"
+            verify_ed25519(&allocator_key, &voucher.encode(), &signature),
+            SaleError::InvalidVoucher
         );
 
-        let tokens_to_buy_in_gear = msg::value();
-
-        let reply: FTEvent = msg::send_for_reply_as::<_, FTEvent>(
-            self.token,
-            FTAction::Decimals,
-            0,
-        )
-        .unwrap()
-        .await
-        .expect("Function call error");
+        match self.registration.vouchers.get(&msg::source()) {
+            Some(issued_at) if voucher.issued_at < *issued_at => return Err(SaleError::VoucherSuperseded),
+            Some(issued_at) if voucher.issued_at == *issued_at => {},
+            _ => {
+                self.registration.vouchers.insert(msg::source(), voucher.issued_at);
+                self.registration.users.insert(msg::source(), voucher.max_allocation);
+            },
+        }
 
-        let decimals = if let FTEvent::Decimals(decimals) = reply { decimals } else { panic!("Error while parsing event") } as u32;
+        Ok(())
+    }
 
-        let tokens_to_buy = tokens_to_buy_in_gear
-            .saturating_mul(10_u128.pow(decimals))
-            .saturating_div(self.token_price_in_gear);
+    /// Buys tokens for the payment. Returns the event to reply with, the change
+    /// and whether it's the first purchase of the caller.
+    fn buy(
+        &mut self,
+        token_unit: u128,
+        is_fcfs_round: bool,
+        currency: Currency,
+        payment: u128
+    ) -> Result<(SaleEvent, u128, bool), SaleError> {
+        let (participation, is_first_purchase) = self.participation_of_source();
+        let price = self.price_of(currency);
+
+        let tokens_to_buy = mul_div(payment, token_unit, price);
+
+        // Rounded up, so the rounding never goes to the buyer's favour, while still
+        // never exceeding the payment.
+        let tokens_to_buy_in_gear = mul_div_ceil(tokens_to_buy, price, token_unit);
+        let change = payment.saturating_sub(tokens_to_buy_in_gear);
+
+        let (bought_in_round, max_allocation_size) = if is_fcfs_round {
+            (*self.fcfs.purchases.get(&msg::source()).unwrap_or(&0), self.fcfs.max_allocation)
+        } else {
+            (participation.amount_bought, self.max_allocation_of(&msg::source()))
+        };
 
         require!(
-            tokens_to_buy <= *self.registration.users.get(&msg::source()).unwrap(),
-            "You cannot realize allocation greater than your max allocation size"
+            bought_in_round.saturating_add(tokens_to_buy) <= max_allocation_size,
+            SaleError::AllocationExceeded
         );
 
-        require!(tokens_to_buy > 0, "It is impossible to buy zero amount of tokens");
+        require!(tokens_to_buy > 0, SaleError::ZeroAmount);
         require!(
             tokens_to_buy <= self.tokens_to_sell.saturating_sub(self.tokens_sold),
-            "Not enough tokens to sell"
+            SaleError::NotEnoughTokens
         );
 
         self.tokens_sold = self.tokens_sold.saturating_add(tokens_to_buy);
-        self.tokens_raised = self.tokens_raised.saturating_add(tokens_to_buy_in_gear);
-        self.registration_fees = self.registration_fees.saturating_sub(self.registration_fee_gear);
+        self.add_raised(currency, tokens_to_buy_in_gear);
+
+        if let Some(auction) = self.auction {
+            self.clearing_price = self.auction_price(&auction);
+        }
+
+        if is_fcfs_round {
+            self.fcfs.purchases.insert(msg::source(), bought_in_round + tokens_to_buy);
+        }
 
         self.sale.participants.insert(msg::source(), Participate { 
-            amount_bought: tokens_to_buy,
-            amount_paid_gear: tokens_to_buy_in_gear,
-            participated_datetime: exec::block_timestamp()
+            amount_bought: participation.amount_bought.saturating_add(tokens_to_buy),
+            amount_paid_gear: participation.amount_paid_gear.saturating_add(tokens_to_buy_in_gear),
+            currency,
+            ..participation
         });
 
-        msg::send_for_reply(
-            msg::source(),
-            SaleEvent::RegistrationGEARRefunded(msg::source(), self.registration_fee_gear), 
-            self.registration_fee_gear)
-            .unwrap()
-            .await
-            .expect("Sending message error");
+        let event = SaleEvent::TokensPurchased(msg::source(), tokens_to_buy, tokens_to_buy_in_gear, change);
+
+        Ok((event, change, is_first_purchase))
     }
 
-    pub fn remove_registered(&mut self, who: ActorId) {
-        require!(exec::block_timestamp() >= self.registration.start_datetime &&
-                 exec::block_timestamp() <= self.registration.end_datetime,
-            "Registration round is over"
-        );
+    /// In overflow mode the payment is only committed, tokens are allocated by `settle`.
+    fn commit(&mut self, currency: Currency, payment: u128) -> Result<(SaleEvent, u128, bool), SaleError> {
+        require!(payment > 0, SaleError::ZeroAmount);
+
+        let (participation, is_first_purchase) = self.participation_of_source();
+
+        self.add_raised(currency, payment);
+
+        self.sale.participants.insert(msg::source(), Participate { 
+            amount_paid_gear: participation.amount_paid_gear.saturating_add(payment),
+            currency,
+            ..participation
+        });
+
+        Ok((SaleEvent::GearCommitted(msg::source(), payment), 0, is_first_purchase))
+    }
+
+    /// Takes the payment for a purchase: the attached GEAR or the tokens pulled from the caller.
+    async fn receive_payment(&self, token_payment: Option<(ActorId, u128)>) -> Result<u128, SaleError> {
+        match token_payment {
+            None => Ok(msg::value()),
+            Some((token, amount)) => {
+                require!(msg::value() == 0, SaleError::WrongPaymentMethod);
+
+                transfer_tokens(
+                    &token, 
+                    &msg::source(), 
+                    &exec::program_id(), 
+                    amount)
+                    .await;
+
+                Ok(amount)
+            },
+        }
+    }
+
+    /// Replies with `event`, paying `amount` of `currency` to the caller.
+    async fn reply_with_payment(&self, currency: Currency, event: SaleEvent, amount: u128) {
+        match currency {
+            Currency::Gear => {
+                msg::reply(event, amount).unwrap();
+            },
+            Currency::Token(payment_token) => {
+                msg::reply(event, 0).unwrap();
+
+                if amount > 0 {
+                    transfer_tokens(
+                        &payment_token, 
+                        &exec::program_id(), 
+                        &msg::source(), 
+                        amount)
+                        .await;
+                }
+            },
+        }
+    }
+
+    /// Splits `tokens_to_sell` pro rata to the committed GEAR if the sale is oversubscribed,
+    /// otherwise fills every commitment at the sale price. A uniformly cleared auction
+    /// reprices every purchase at the clearing price. Unspent GEAR is left for `claim_refund`.
+    pub async fn settle(&mut self) -> Result<(), SaleError> {
+        require!(self.needs_settlement(), SaleError::NothingToSettle);
+        self.only_in_phase(&[SalePhase::Ended])?;
+
+        let token_unit = self.token_unit().await;
+
+        // Could have been settled by another message while waiting for decimals.
+        self.only_in_phase(&[SalePhase::Ended])?;
+
+        // Repricing at the clearing price can take a uniformly cleared auction below the soft cap.
+        if !self.overflow && mul_div(self.tokens_sold, self.clearing_price, token_unit) < self.soft_cap {
+            self.transition(SalePhase::Failed)?;
+
+            msg::reply(SaleEvent::SaleFailed(exec::block_timestamp()), 0).unwrap();
+
+            return Ok(());
+        }
+
+        // Commitments in different currencies are compared by their value in the base one.
+        let base_price = self.prices[&self.base_currency];
+        let total_committed = self.tokens_raised;
+        let oversubscribed = mul_div(total_committed, token_unit, base_price) > self.tokens_to_sell;
+
+        let mut tokens_sold: u128 = 0;
+        let mut raised: BTreeMap<Currency, u128> = BTreeMap::new();
+
+        for participation in self.sale.participants.values_mut() {
+            let committed = participation.amount_paid_gear;
+            let price = if self.overflow {
+                self.prices[&participation.currency]
+            } else {
+                mul_div(self.prices[&participation.currency], self.clearing_price, base_price)
+            };
+
+            let bought = if !self.overflow {
+                participation.amount_bought
+            } else if oversubscribed {
+                mul_div(self.tokens_to_sell, mul_div(committed, base_price, price), total_committed)
+            } else {
+                mul_div(committed, token_unit, price)
+            };
+            let spent = mul_div_ceil(bought, price, token_unit).min(committed);
+
+            participation.amount_bought = bought;
+            participation.amount_paid_gear = spent;
+            participation.overflow_refund_gear += committed - spent;
+
+            tokens_sold = tokens_sold.saturating_add(bought);
+
+            let raised_in_currency = raised.entry(participation.currency).or_insert(0);
+            *raised_in_currency = raised_in_currency.saturating_add(spent);
+        }
+
+        self.tokens_sold = tokens_sold;
+        self.tokens_raised = 0;
+        self.raised.clear();
+        for (currency, amount) in raised {
+            self.add_raised(currency, amount);
+        }
+        self.settled = true;
+        self.transition(SalePhase::Finalized)?;
+
+        msg::reply(SaleEvent::SaleSettled(tokens_sold, self.tokens_raised), 0).unwrap();
+
+        Ok(())
+    }
+
+    /// Callable by anyone. Nothing hooks into staking, so this is the only point where an unstake
+    /// made after buying takes effect, the keeper running it is part of operating the sale.
+    pub async fn check_stake(&mut self, who: ActorId) -> Result<(), SaleError> {
+        self.only_in_phase(&[SalePhase::Registration, SalePhase::RegistrationEnded, SalePhase::Sale])?;
+        require!(self.registration.users.get(&who).is_some(), SaleError::NotRegistered);
+
+        let stake = self.sync_stake(who).await;
+
+        msg::reply(SaleEvent::StakeChecked(who, stake, self.max_allocation_of(&who)), 0).unwrap();
+
+        Ok(())
+    }
+
+    /// Compares the current stake of a registered user with the snapshot taken on registration.
+    /// If it went down, the user drops to the matching tier, or their pushed allocation shrinks
+    /// in the same proportion. Returns the current stake.
+    async fn sync_stake(&mut self, who: ActorId) -> u128 {
+        let stake = self.stake_of(who).await;
+
+        let registered_stake = *self.registration.stakes.get(&who).unwrap_or(&0);
+        if stake >= registered_stake {
+            return stake;
+        }
+
+        if self.tiers.is_empty() {
+            if let Some(allocation) = self.registration.users.get_mut(&who) {
+                *allocation = mul_div(*allocation, stake, registered_stake);
+            }
+        } else if let Some(old_tier) = self.registration.user_tiers.remove(&who) {
+            self.registration.total_weight -= self.tiers[old_tier as usize].weight;
+
+            if let Some(tier) = self.tiers.iter().rposition(|tier| stake >= tier.min_stake) {
+                self.registration.user_tiers.insert(who, tier as u32);
+                self.registration.total_weight += self.tiers[tier].weight;
+            }
+        }
+
+        self.registration.stakes.insert(who, stake);
+        self.cut_purchase_to_allocation(&who);
+
+        stake
+    }
+
+    /// Takes back the tokens bought in the sale round above the allocation left after
+    /// a stake drop. The matching part of the payment is refunded through `claim_refund`.
+    fn cut_purchase_to_allocation(&mut self, who: &ActorId) {
+        let allowed = self.max_allocation_of(who);
+        let bought_in_fcfs = *self.fcfs.purchases.get(who).unwrap_or(&0);
+
+        let participation = match self.sale.participants.get_mut(who) {
+            Some(participation) => participation,
+            None => return,
+        };
+
+        let bought_in_round = participation.amount_bought - bought_in_fcfs;
+        if bought_in_round <= allowed {
+            return;
+        }
+
+        let excess = bought_in_round - allowed;
+        let refund = mul_div(participation.amount_paid_gear, excess, participation.amount_bought);
+
+        participation.amount_bought -= excess;
+        participation.amount_paid_gear -= refund;
+        participation.overflow_refund_gear += refund;
+        let currency = participation.currency;
+
+        self.tokens_sold = self.tokens_sold.saturating_sub(excess);
+        self.sub_raised(currency, refund);
+    }
+
+    pub fn remove_registered(&mut self, who: ActorId) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_in_phase(&[SalePhase::Registration])?;
         require!(
-            self.registration.users.get(&msg::source()).is_some(),
-            "User must be registered"
+            self.registration.users.get(&who).is_some(),
+            SaleError::NotRegistered
         );
 
-        self.registration.users.remove(&who);   
+        self.remove_user(&who);
         
         msg::reply(SaleEvent::RegisteredRemoved(who), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn unregister(&mut self) -> Result<(), SaleError> {
+        self.only_if_not_paused()?;
+        self.only_in_phase(&[SalePhase::Registration])?;
+        require!(
+            self.registration.users.get(&msg::source()).is_some(),
+            SaleError::NotRegistered
+        );
+
+        self.remove_user(&msg::source());
+        self.registration_fees = self.registration_fees.saturating_sub(self.registration_fee_gear);
+
+        msg::reply(
+            SaleEvent::UserUnregistered(msg::source(), self.registration_fee_gear),
+            self.registration_fee_gear)
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn remove_user(&mut self, who: &ActorId) {
+        self.registration.users.remove(who);
+        self.registration.stakes.remove(who);
+        self.registration.vouchers.remove(who);
+        if let Some(tier) = self.registration.user_tiers.remove(who) {
+            self.registration.total_weight -= self.tiers[tier as usize].weight;
+        }
     }
 
-    pub async fn deposit_tokens(&mut self) {
-        self.only_sale_owner();
-        self.only_if_gate_open();
+    pub async fn deposit_tokens(&mut self) -> Result<(), SaleError> {
+        self.only_sale_owner()?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[SalePhase::Draft, SalePhase::Configured, SalePhase::Registration])?;
         
-        require!(self.is_created, "Sale must be created");
-        require!(!self.tokens_deposited, "Tokens already deposited");
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(!self.tokens_deposited, SaleError::TokensAlreadyDeposited);
         
         transfer_tokens(
             &self.token, 
@@ -176,138 +571,473 @@ impl Sale {
             .await;
 
         self.tokens_deposited = true;
+
+        Ok(())
     }
 
-    pub async fn withdraw_allocation(&mut self) {
-        require!(exec::block_timestamp() >= self.sale.end_datetime, "Sale is not over yet");
+    pub async fn withdraw_allocation(&mut self) -> Result<(), SaleError> {
+        self.only_if_not_paused()?;
 
-        let participant = self.sale.participants.get(&msg::source());
-        require!(participant.is_some(), "User has to participates sale to be able withdraw funds");
+        let participation = self.participation_to_claim()?;
 
-        let participation = participant.unwrap();
-        require!(participation.amount_bought > 0, "There are no funds to withdraw");
-        
-        transfer_tokens(
-            &self.token, 
-            &exec::program_id(), 
-            &msg::source(), 
-            participation.amount_bought)
-            .await;
+        let to_withdraw = self.vested_amount(&participation).saturating_sub(participation.amount_claimed);
+        require!(to_withdraw > 0, SaleError::NothingToWithdraw);
+
+        self.transfer_claimed(to_withdraw).await?;
 
         msg::send_for_reply(
             msg::source(), 
-            SaleEvent::AllocationWithdrawn(msg::source(), participation.amount_bought), 
+            SaleEvent::AllocationWithdrawn(msg::source(), to_withdraw), 
             0)
             .unwrap()
             .await
             .expect("Sending message error");
+
+        Ok(())
     }
 
-    pub fn withdraw_earnings(&mut self) {
-        self.only_sale_owner();
+    pub async fn claim_allocation(&mut self, amount: u128) -> Result<(), SaleError> {
+        self.only_if_not_paused()?;
+
+        let participation = self.participation_to_claim()?;
+
+        require!(amount > 0, SaleError::ZeroAmount);
+        require!(
+            amount <= self.vested_amount(&participation).saturating_sub(participation.amount_claimed),
+            SaleError::ClaimExceedsClaimable
+        );
 
-        require!(exec::block_timestamp() >= self.sale.end_datetime, "Sale is not over yet");
-        require!(!self.earnings_withdrawn, "Impossible to withdraw earnings twice");
+        self.transfer_claimed(amount).await?;
 
-        require!(self.tokens_raised > 0, "There are no tokens to withdraw");
+        let remaining = participation.amount_bought - participation.amount_claimed - amount;
 
-        msg::reply(SaleEvent::EarningsWithdrawn(self.tokens_raised), self.tokens_raised).unwrap();
+        msg::send_for_reply(
+            msg::source(), 
+            SaleEvent::AllocationClaimed(msg::source(), amount, remaining), 
+            0)
+            .unwrap()
+            .await
+            .expect("Sending message error");
 
-        self.earnings_withdrawn = true;
+        Ok(())
     }
 
-    pub async fn withdraw_leftover(&mut self) {
-        self.only_sale_owner();
+    /// Pays out every share of the earnings not paid yet. A share whose token transfer
+    /// fails stays unpaid, so calling it again retries it.
+    pub async fn withdraw_earnings(&mut self) -> Result<(), SaleError> {
+        self.only_sale_owner()?;
+        self.only_if_not_paused()?;
+        self.only_in_phase(&[SalePhase::Finalized])?;
+
+        let payouts = self.earnings_payouts();
+        require!(!payouts.is_empty(), SaleError::NothingToWithdraw);
+
+        let pending: Vec<_> = payouts
+            .into_iter()
+            .filter(|(slot, _, currency, _)| !self.earnings_paid.contains(&(*currency, *slot)))
+            .collect();
+        require!(!pending.is_empty(), SaleError::EarningsAlreadyWithdrawn);
+
+        // Recorded before the awaits, so a second message can't pay the same share meanwhile.
+        for (slot, _, currency, _) in &pending {
+            self.earnings_paid.insert((*currency, *slot));
+        }
 
-        require!(exec::block_timestamp() >= self.sale.end_datetime, "Sale is not over yet");
-        require!(!self.leftover_withdrawn, "Impossible to withdraw leftover twice");
+        let mut owner_earnings = Vec::new();
+        for (slot, recipient, currency, amount) in pending {
+            let paid = match currency {
+                // GEAR is attached to the messages below.
+                Currency::Gear => true,
+                Currency::Token(token) => try_transfer_tokens(
+                    &token, 
+                    &exec::program_id(), 
+                    &recipient.unwrap_or(self.owner), 
+                    amount)
+                    .await,
+            };
+
+            if !paid {
+                self.earnings_paid.remove(&(currency, slot));
+                continue;
+            }
 
-        let leftover = self.tokens_to_sell.saturating_sub(self.tokens_sold);
-        require!(leftover > 0, "There are no tokens to withdraw");
+            match recipient {
+                Some(recipient) => {
+                    let value = if currency == Currency::Gear { amount } else { 0 };
+                    msg::send(recipient, SaleEvent::EarningsSplit(recipient, currency, amount), value).unwrap();
+                },
+                None => owner_earnings.push((currency, amount)),
+            }
+        }
 
-        transfer_tokens(
+        let earnings_in_gear = owner_earnings
+            .iter()
+            .find(|(currency, _)| *currency == Currency::Gear)
+            .map_or(0, |(_, amount)| *amount);
+        msg::reply(SaleEvent::EarningsWithdrawn(owner_earnings), earnings_in_gear).unwrap();
+
+        Ok(())
+    }
+
+    pub async fn withdraw_leftover(&mut self) -> Result<(), SaleError> {
+        self.only_sale_owner()?;
+        self.only_if_not_paused()?;
+        self.only_in_phase(&[SalePhase::Finalized, SalePhase::Failed, SalePhase::Cancelled])?;
+        require!(self.tokens_deposited, SaleError::TokensNotDeposited);
+        require!(!self.leftover_withdrawn, SaleError::LeftoverAlreadyWithdrawn);
+
+        let leftover = if matches!(self.phase, SalePhase::Failed | SalePhase::Cancelled) {
+            self.tokens_to_sell
+        } else {
+            self.tokens_to_sell.saturating_sub(self.tokens_sold)
+        };
+        require!(leftover > 0, SaleError::NothingToWithdraw);
+
+        // Recorded before the await, so a second message can't withdraw it again meanwhile.
+        self.leftover_withdrawn = true;
+
+        let transferred = try_transfer_tokens(
             &self.token, 
             &exec::program_id(), 
             &msg::source(), 
             leftover)
             .await;
 
-        self.leftover_withdrawn = true;
+        if !transferred {
+            self.leftover_withdrawn = false;
+        }
+        require!(transferred, SaleError::TransferFailed);
+
+        Ok(())
+    }
+
+    pub async fn claim_refund(&mut self) -> Result<(), SaleError> {
+        self.only_if_not_paused()?;
+        self.only_in_phase(&[SalePhase::Finalized, SalePhase::Failed, SalePhase::Cancelled])?;
+        if self.phase == SalePhase::Cancelled {
+            return self.claim_cancellation_refund().await;
+        }
+
+        let is_failed = self.phase == SalePhase::Failed;
+
+        let participant = self.sale.participants.get_mut(&msg::source());
+        require!(participant.is_some(), SaleError::NotParticipant);
+
+        let participation = participant.unwrap();
+        require!(!participation.refunded, SaleError::AlreadyRefunded);
+
+        let refund = if is_failed {
+            participation.amount_paid_gear + participation.overflow_refund_gear
+        } else {
+            participation.overflow_refund_gear
+        };
+        require!(refund > 0, SaleError::NothingToRefund);
+
+        participation.refunded = true;
+        let currency = participation.currency;
+
+        self.reply_with_payment(currency, SaleEvent::RefundClaimed(msg::source(), refund), refund).await;
+
+        Ok(())
     }
 
-    pub fn withdraw_registration_fees(&mut self) {
-        self.only_admin();
+    /// Participants get back all they paid. Registrants who haven't bought anything
+    /// get back their fee, the others already had it returned with their first purchase.
+    async fn claim_cancellation_refund(&mut self) -> Result<(), SaleError> {
+        let refund = match self.sale.participants.get(&msg::source()) {
+            Some(participation) => {
+                require!(!participation.refunded, SaleError::AlreadyRefunded);
+                participation.amount_paid_gear + participation.overflow_refund_gear
+            },
+            None => {
+                require!(self.registration.users.get(&msg::source()).is_some(), SaleError::NotParticipant);
+                require!(!self.registration.refunded_fees.contains(&msg::source()), SaleError::AlreadyRefunded);
+                self.registration_fee_gear
+            },
+        };
+        require!(refund > 0, SaleError::NothingToRefund);
+
+        if let Some(participation) = self.sale.participants.get_mut(&msg::source()) {
+            participation.refunded = true;
+            let currency = participation.currency;
 
-        require!(exec::block_timestamp() >= self.sale.end_datetime, "Sale is not over yet");
-        require!(self.registration_fees > 0, "There are no tokens to withdraw");
+            self.reply_with_payment(currency, SaleEvent::RefundClaimed(msg::source(), refund), refund).await;
+        } else {
+            self.registration.refunded_fees.insert(msg::source());
+            self.registration_fees = self.registration_fees.saturating_sub(refund);
+
+            msg::reply(SaleEvent::RefundClaimed(msg::source(), refund), refund).unwrap();
+        }
+
+        Ok(())
+    }
+
+    pub fn withdraw_registration_fees(&mut self) -> Result<(), SaleError> {
+        self.only_role(Role::Treasurer)?;
+        self.only_if_not_paused()?;
+        self.only_in_phase(&[SalePhase::Ended, SalePhase::Finalized, SalePhase::Failed])?;
+
+        require!(self.registration_fees > 0, SaleError::NothingToWithdraw);
 
         msg::reply(SaleEvent::RegistrationFeeWithdrawn(self.registration_fees), self.registration_fees).unwrap();
 
         self.registration_fees = 0;
+
+        Ok(())
     }
 
-    pub fn close_gate(&mut self) {
-        self.only_admin();
-        self.only_if_gate_open();
+    pub fn close_gate(&mut self) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[SalePhase::Draft, SalePhase::Configured, SalePhase::Registration])?;
 
-        require!(self.is_created, "Sale is not created");
-        require!(self.token != ZERO_ID, "Token is not set");
-        require!(self.tokens_deposited, "Tokens are not deposited");
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(self.token != ZERO_ID, SaleError::TokenNotSet);
+        require!(self.tokens_deposited, SaleError::TokensNotDeposited);
         require!(
             self.registration.start_datetime != 0 && self.registration.end_datetime != 0,
-            "Registration params are not set"
+            SaleError::RegistrationTimeNotSet
         );
 
         self.gate_closed = true;
 
         msg::reply(SaleEvent::GateClosed(exec::block_timestamp()), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn cancel(&mut self) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_in_phase(&[
+            SalePhase::Draft,
+            SalePhase::Configured,
+            SalePhase::Registration,
+            SalePhase::RegistrationEnded,
+            SalePhase::Sale,
+        ])?;
+
+        self.transition(SalePhase::Cancelled)
+    }
+
+    pub fn pause(&mut self) -> Result<(), SaleError> {
+        self.only_role(Role::Pauser)?;
+
+        require!(self.paused_at.is_none(), SaleError::AlreadyPaused);
+
+        self.paused_at = Some(exec::block_timestamp());
+
+        msg::reply(SaleEvent::Paused(exec::block_timestamp()), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn unpause(&mut self, extend_round: bool) -> Result<(), SaleError> {
+        self.only_role(Role::Pauser)?;
+
+        let paused_at = self.paused_at.ok_or(SaleError::NotPaused)?;
+        let extension = if extend_round { exec::block_timestamp() - paused_at } else { 0 };
+
+        self.postpone(paused_at, extension);
+        self.paused_at = None;
+
+        msg::reply(SaleEvent::Unpaused(exec::block_timestamp(), extension), 0).unwrap();
+
+        Ok(())
+    }
+
+    /// Shifts every round boundary not yet reached at `since` by `duration`,
+    /// so the active round gets longer and the following ones move along.
+    fn postpone(&mut self, since: u64, duration: u64) {
+        let datetimes = [
+            &mut self.registration.start_datetime,
+            &mut self.registration.end_datetime,
+            &mut self.sale.start_datetime,
+            &mut self.sale.end_datetime,
+            &mut self.fcfs.start_datetime,
+            &mut self.fcfs.end_datetime,
+        ];
+
+        for datetime in datetimes {
+            if *datetime >= since {
+                *datetime = datetime.saturating_add(duration);
+            }
+        }
+    }
+
+    pub fn propose_admin(&mut self, admin: ActorId) -> Result<(), SaleError> {
+        self.only_admin()?;
+
+        require!(admin != ZERO_ID, SaleError::InvalidAdmin);
+
+        self.pending_admin = Some(admin);
+
+        msg::reply(SaleEvent::AdminProposed(admin), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn accept_admin(&mut self) -> Result<(), SaleError> {
+        require!(self.pending_admin == Some(msg::source()), SaleError::NotPendingAdmin);
+
+        self.admin = msg::source();
+        self.pending_admin = None;
+
+        msg::reply(SaleEvent::AdminChanged(msg::source()), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn set_allocation_sizes(&mut self, allocations_sizes: BTreeMap<ActorId, u128>) {
-        self.only_admin();   
+    /// Only the owner rotates their own key, the admin can't take over the proceeds.
+    pub fn propose_owner(&mut self, owner: ActorId) -> Result<(), SaleError> {
+        self.only_sale_owner()?;
 
-        for (user, allocation_size) in allocations_sizes.iter() {
-            let registered_user = self.registration.users.get(user);
-            match registered_user {
-                Some(_) => {
-                    self.registration.users
-                        .entry(*user)
-                        .and_modify(|allocation| *allocation += allocation_size)
-                        .or_insert(*allocation_size);
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(owner != ZERO_ID, SaleError::InvalidSaleOwner);
 
-                        msg::reply(SaleEvent::MaxAllocationSizeSet((*user, *allocation_size)), 0).unwrap();
+        self.pending_owner = Some(owner);
+
+        msg::reply(SaleEvent::OwnerProposed(owner), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn accept_owner(&mut self) -> Result<(), SaleError> {
+        require!(self.pending_owner == Some(msg::source()), SaleError::NotPendingOwner);
+
+        self.owner = msg::source();
+        self.pending_owner = None;
+
+        msg::reply(SaleEvent::OwnerChanged(msg::source()), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn grant_role(&mut self, role: Role, who: ActorId) -> Result<(), SaleError> {
+        self.only_admin()?;
+
+        self.roles.insert((role, who));
+
+        msg::reply(SaleEvent::RoleGranted(role, who), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn revoke_role(&mut self, role: Role, who: ActorId) -> Result<(), SaleError> {
+        self.only_admin()?;
+
+        require!(self.roles.remove(&(role, who)), SaleError::MissingRole(role));
+
+        msg::reply(SaleEvent::RoleRevoked(role, who), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_allocation_sizes(&mut self, mode: AllocationMode, allocations_sizes: BTreeMap<ActorId, u128>) -> Result<(), SaleError> {
+        self.only_role(Role::Allocator)?;   
+        self.only_in_phase(&[SalePhase::Registration, SalePhase::RegistrationEnded, SalePhase::Sale])?;
+
+        require!(self.tiers.is_empty(), SaleError::AllocationsDerivedFromTiers);
+        require!(!self.overflow, SaleError::AllocationsUnusedInOverflow);
+
+        self.apply_allocation_sizes(None, mode, allocations_sizes);
+
+        Ok(())
+    }
+
+    pub fn upload_allocation_chunk(&mut self, chunk: AllocationChunk) -> Result<(), SaleError> {
+        self.only_role(Role::Allocator)?;
+        self.only_in_phase(&[SalePhase::Registration, SalePhase::RegistrationEnded, SalePhase::Sale])?;
+
+        require!(self.tiers.is_empty(), SaleError::AllocationsDerivedFromTiers);
+        require!(!self.overflow, SaleError::AllocationsUnusedInOverflow);
+        require!(
+            self.registration.uploaded_chunks.insert((chunk.upload_id, chunk.index)),
+            SaleError::ChunkAlreadyUploaded
+        );
+
+        self.apply_allocation_sizes(Some((chunk.upload_id, chunk.index)), chunk.mode, chunk.allocations);
+
+        Ok(())
+    }
+
+    /// Updates allocation sizes of registered users, skipping unknown ones, and replies with a summary.
+    fn apply_allocation_sizes(
+        &mut self,
+        chunk: Option<(u32, u32)>,
+        mode: AllocationMode,
+        allocations_sizes: BTreeMap<ActorId, u128>
+    ) {
+        let mut summary = AllocationUploadSummary {
+            chunk,
+            ..Default::default()
+        };
+
+        for (user, allocation_size) in allocations_sizes {
+            match self.registration.users.get_mut(&user) {
+                Some(allocation) => {
+                    let previous = *allocation;
+
+                    *allocation = match mode {
+                        AllocationMode::Set => allocation_size,
+                        AllocationMode::Increase => previous.saturating_add(allocation_size),
+                        AllocationMode::Decrease => previous.saturating_sub(allocation_size),
+                    };
+
+                    summary.applied.push(AllocationChange { user, previous, new: *allocation });
                 },
-                None => panic!("User is not registered"),
+                None => summary.rejected.push(user),
             }
         }
+
+        msg::reply(SaleEvent::MaxAllocationSizesSet(summary), 0).unwrap();
     }
 
-    pub fn set_sale_parameters(&mut self, parameters: SaleParameters) {
-        self.only_admin();
+    pub fn set_sale_parameters(&mut self, parameters: SaleParameters) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_in_phase(&[SalePhase::Draft])?;
 
-        require!(!self.is_created, "Sale must not be created");
-        require!(parameters.owner != ZERO_ID, "Invalid sale owner address");
-        require!(parameters.tokens_to_sell > 0, "Amout of tokens must be greater than zero");
+        require!(!self.is_created, SaleError::SaleAlreadyCreated);
+        require!(parameters.owner != ZERO_ID, SaleError::InvalidSaleOwner);
+        require!(parameters.tokens_to_sell > 0, SaleError::ZeroTokensToSell);
+        if let Some(vesting) = parameters.vesting {
+            require!(vesting.tge_unlock_percent <= 100, SaleError::InvalidVesting);
+        }
+        require!(
+            !parameters.overflow || parameters.allocator_key.is_none(),
+            SaleError::AllocationsUnusedInOverflow
+        );
 
         self.owner = parameters.owner;
         self.token = parameters.token;
         self.staking = parameters.staking;
-        self.token_price_in_gear = parameters.token_price_in_gear;
+        self.base_currency = parameters.payment_token.map_or(Currency::Gear, Currency::Token);
+        self.prices.insert(self.base_currency, parameters.token_price_in_gear);
         self.tokens_to_sell = parameters.tokens_to_sell;
         self.registration_fee_gear = parameters.registration_fee_gear;
+        self.soft_cap = parameters.soft_cap;
+        self.overflow = parameters.overflow;
+        self.vesting = parameters.vesting;
+        self.whitelist_root = parameters.whitelist_root;
+        self.allocator_key = parameters.allocator_key;
 
         self.is_created = true;
 
         msg::reply(SaleEvent::SaleCreated(parameters.clone()), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn set_registration_time(&mut self, start_datetime: u64, end_datetime: u64) {
-        self.only_admin();
-        self.only_if_gate_open();
+    pub fn set_registration_time(&mut self, start_datetime: u64, end_datetime: u64) -> Result<(), SaleError> {
+        self.only_role(Role::Scheduler)?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[SalePhase::Draft, SalePhase::Configured])?;
 
-        require!(self.is_created, "Sale must be created");
-        require!(self.sale.end_datetime > end_datetime, "Registration end date must be earlier than sale's end date");
-        require!(start_datetime >= exec::block_timestamp() && start_datetime < end_datetime, "Registration's start date must be in future");
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(
+            self.sale.start_datetime == 0 || end_datetime < self.sale.start_datetime,
+            SaleError::InvalidRegistrationTime
+        );
+        require!(start_datetime >= exec::block_timestamp() && start_datetime < end_datetime, SaleError::InvalidRegistrationTime);
 
         self.registration = RegistrationRound {
             start_datetime,
@@ -316,68 +1046,572 @@ impl Sale {
         };
 
         msg::reply(SaleEvent::RegistrationTimeSet(exec::block_timestamp()), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn set_sale_time(&mut self, start_datetime: u64, end_datetime: u64) {
-        self.only_admin();
-        self.only_if_gate_open();
+    pub fn set_sale_time(&mut self, start_datetime: u64, end_datetime: u64) -> Result<(), SaleError> {
+        self.only_role(Role::Scheduler)?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[SalePhase::Draft, SalePhase::Configured])?;
 
-        require!(self.is_created, "Sale must be created");
-        require!(start_datetime >= exec::block_timestamp() && start_datetime < end_datetime, "Sale's start date must be in future");
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(start_datetime >= exec::block_timestamp() && start_datetime < end_datetime, SaleError::InvalidSaleTime);
+        require!(
+            self.registration.end_datetime == 0 || start_datetime > self.registration.end_datetime,
+            SaleError::InvalidSaleTime
+        );
+        require!(
+            self.fcfs.end_datetime == 0 || end_datetime <= self.fcfs.start_datetime,
+            SaleError::InvalidSaleTime
+        );
 
         self.sale.start_datetime = start_datetime;
         self.sale.end_datetime = end_datetime;
 
         msg::reply(SaleEvent::SaleTimeSet(exec::block_timestamp()), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn set_sale_token(&mut self, sale_token: ActorId) {
-        self.only_admin();
-        self.only_if_gate_open();
+    pub fn set_fcfs_round(&mut self, parameters: FcfsParameters) -> Result<(), SaleError> {
+        self.only_role(Role::Scheduler)?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[
+            SalePhase::Draft,
+            SalePhase::Configured,
+            SalePhase::Registration,
+            SalePhase::RegistrationEnded,
+        ])?;
+
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(!self.overflow, SaleError::InvalidFcfsRound);
+        require!(
+            self.sale.end_datetime != 0 && parameters.start_datetime >= self.sale.end_datetime,
+            SaleError::InvalidFcfsRound
+        );
+        require!(parameters.start_datetime < parameters.end_datetime, SaleError::InvalidFcfsRound);
+        require!(parameters.max_allocation > 0, SaleError::InvalidFcfsRound);
+
+        self.fcfs = FcfsRound {
+            start_datetime: parameters.start_datetime,
+            end_datetime: parameters.end_datetime,
+            max_allocation: parameters.max_allocation,
+            open_to_all: parameters.open_to_all,
+            ..Default::default()
+        };
+
+        msg::reply(SaleEvent::FcfsRoundSet(exec::block_timestamp()), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_tiers(&mut self, tiers: Vec<Tier>) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[
+            SalePhase::Draft,
+            SalePhase::Configured,
+            SalePhase::Registration,
+            SalePhase::RegistrationEnded,
+        ])?;
+
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(!self.overflow, SaleError::AllocationsUnusedInOverflow);
+        require!(self.registration.users.is_empty(), SaleError::TiersLocked);
+        require!(
+            tiers.windows(2).all(|pair| pair[0].min_stake < pair[1].min_stake),
+            SaleError::InvalidTiers
+        );
+        require!(tiers.iter().all(|tier| tier.weight > 0), SaleError::InvalidTiers);
+
+        self.tiers = tiers;
+
+        msg::reply(SaleEvent::TiersSet(exec::block_timestamp()), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn add_payment_currency(&mut self, currency: Currency, price: u128) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[
+            SalePhase::Draft,
+            SalePhase::Configured,
+            SalePhase::Registration,
+            SalePhase::RegistrationEnded,
+        ])?;
+
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(price > 0, SaleError::InvalidPrice);
+
+        self.prices.insert(currency, price);
+
+        msg::reply(SaleEvent::PaymentCurrencyAdded(currency, price), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_platform_fee(&mut self, fee: PlatformFee) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[
+            SalePhase::Draft,
+            SalePhase::Configured,
+            SalePhase::Registration,
+            SalePhase::RegistrationEnded,
+        ])?;
+
+        require!(self.is_created, SaleError::SaleNotCreated);
+
+        let total_bps = fee.recipients
+            .iter()
+            .fold(fee.fee_bps as u128, |total, (_, share_bps)| total + *share_bps as u128);
+        require!(total_bps <= BPS_DENOMINATOR, SaleError::InvalidPlatformFee);
+        require!(fee.fee_bps == 0 || fee.treasury != ZERO_ID, SaleError::InvalidPlatformFee);
+        require!(
+            fee.recipients.iter().all(|(recipient, _)| *recipient != ZERO_ID),
+            SaleError::InvalidPlatformFee
+        );
+
+        self.platform_fee = Some(fee.clone());
+
+        msg::reply(SaleEvent::PlatformFeeSet(fee), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_dutch_auction(&mut self, auction: DutchAuction) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[
+            SalePhase::Draft,
+            SalePhase::Configured,
+            SalePhase::Registration,
+            SalePhase::RegistrationEnded,
+        ])?;
+
+        require!(self.is_created, SaleError::SaleNotCreated);
+        require!(!self.overflow, SaleError::InvalidDutchAuction);
+        require!(
+            auction.floor_price > 0 && auction.floor_price < auction.start_price,
+            SaleError::InvalidDutchAuction
+        );
+        require!(auction.decay != PriceDecay::Steps(0), SaleError::InvalidDutchAuction);
+
+        self.auction = Some(auction);
+
+        msg::reply(SaleEvent::DutchAuctionSet(auction), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_sale_token(&mut self, sale_token: ActorId) -> Result<(), SaleError> {
+        self.only_admin()?;
+        self.only_if_gate_open()?;
+        self.only_in_phase(&[SalePhase::Draft, SalePhase::Configured])?;
 
         self.token = sale_token;
 
         msg::reply(SaleEvent::SaleTokenSet(sale_token), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn get_allocation_size_of(&self, participiant: ActorId) {
-        let allocation_size = self.registration.users
-            .get(&participiant)
-            .expect("Polkapad Sale: user is not registered to the sale");
+    pub fn get_allocation_size_of(&self, participiant: ActorId) -> Result<(), SaleError> {
+        require!(
+            self.registration.users.get(&participiant).is_some(),
+            SaleError::NotRegistered
+        );
         
-        msg::reply(SaleEvent::AllocationSize(*allocation_size), 0).unwrap();
+        msg::reply(SaleEvent::AllocationSize(self.max_allocation_of(&participiant)), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn get_participation_of(&self, participiant: ActorId) {
+    /// Allocation pushed by admin or, when tiers are set, the user's share of
+    /// `tokens_to_sell` by the weight of their tier.
+    pub fn max_allocation_of(&self, who: &ActorId) -> u128 {
+        if self.tiers.is_empty() {
+            return *self.registration.users.get(who).unwrap_or(&0);
+        }
+
+        let total_weight = if self.sale.total_weight != 0 {
+            self.sale.total_weight
+        } else {
+            self.registration.total_weight
+        };
+
+        match self.registration.user_tiers.get(who) {
+            Some(tier) => mul_div(self.tokens_to_sell, self.tiers[*tier as usize].weight, total_weight),
+            None => 0,
+        }
+    }
+
+    pub fn get_participation_of(&self, participiant: ActorId) -> Result<(), SaleError> {
         let participation = self.sale.participants
             .get(&participiant)
-            .expect("Polkapad Sale:  user is not a participiant of this sale");
+            .ok_or(SaleError::NotParticipant)?;
         
         msg::reply(SaleEvent::Participation(*participation), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn get_sale_token(&self) {
+    pub fn get_claimable_of(&self, participiant: ActorId) -> Result<(), SaleError> {
+        msg::reply(SaleEvent::Claimable(self.claimable_of(&participiant)), 0).unwrap();
+
+        Ok(())
+    }
+
+    /// Vested and not yet claimed amount of `participiant`.
+    pub fn claimable_of(&self, participiant: &ActorId) -> u128 {
+        match self.sale.participants.get(participiant) {
+            Some(participation) if self.phase() == SalePhase::Finalized =>
+                self.vested_amount(participation).saturating_sub(participation.amount_claimed),
+            _ => 0,
+        }
+    }
+
+    pub fn get_sale_token(&self) -> Result<(), SaleError> {
         msg::reply(SaleEvent::SaleToken(self.token), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn get_total_raised(&self) {
-        msg::reply(SaleEvent::TotalRaised(self.tokens_raised), 0).unwrap();
+    pub fn get_total_raised(&self) -> Result<(), SaleError> {
+        msg::reply(SaleEvent::TotalRaised(self.raised_by_currency()), 0).unwrap();
+
+        Ok(())
     }
 
-    pub fn get_total_sold(&self) {
+    pub fn get_total_sold(&self) -> Result<(), SaleError> {
         msg::reply(SaleEvent::TotalSold(self.tokens_sold), 0).unwrap();
+
+        Ok(())
+    }
+
+    pub fn raised_by_currency(&self) -> Vec<(Currency, u128)> {
+        self.raised.iter().map(|(currency, amount)| (*currency, *amount)).collect()
+    }
+
+    /// Books `amount` of `currency` in the ledger and, converted, in the base currency total.
+    fn add_raised(&mut self, currency: Currency, amount: u128) {
+        let raised = self.raised.entry(currency).or_insert(0);
+        *raised = raised.saturating_add(amount);
+
+        let in_base_currency = mul_div(amount, self.prices[&self.base_currency], self.prices[&currency]);
+        self.tokens_raised = self.tokens_raised.saturating_add(in_base_currency);
+    }
+
+    /// Price of a whole token in `currency` at the current time.
+    pub fn price_of(&self, currency: Currency) -> u128 {
+        match &self.auction {
+            Some(auction) => mul_div(
+                self.prices[&currency],
+                self.auction_price(auction),
+                self.prices[&self.base_currency],
+            ),
+            None => self.prices[&currency],
+        }
+    }
+
+    /// Base currency price of the auction, it stays at the floor after the sale round.
+    fn auction_price(&self, auction: &DutchAuction) -> u128 {
+        let duration = self.sale.end_datetime.saturating_sub(self.sale.start_datetime) as u128;
+        let elapsed = (exec::block_timestamp().saturating_sub(self.sale.start_datetime) as u128).min(duration);
+        if duration == 0 {
+            return auction.floor_price;
+        }
+
+        let drop = auction.start_price - auction.floor_price;
+        let dropped = match auction.decay {
+            PriceDecay::Linear => mul_div(drop, elapsed, duration),
+            PriceDecay::Steps(steps) => {
+                let steps_passed = mul_div(steps as u128, elapsed, duration);
+                mul_div(drop, steps_passed, steps as u128)
+            },
+        };
+
+        auction.start_price - dropped
+    }
+
+    fn needs_settlement(&self) -> bool {
+        self.overflow || matches!(self.auction, Some(auction) if auction.uniform_clearing)
+    }
+
+    fn sub_raised(&mut self, currency: Currency, amount: u128) {
+        let raised = self.raised.entry(currency).or_insert(0);
+        *raised = raised.saturating_sub(amount);
+
+        let in_base_currency = mul_div(amount, self.prices[&self.base_currency], self.prices[&currency]);
+        self.tokens_raised = self.tokens_raised.saturating_sub(in_base_currency);
+    }
+
+    fn participation_of_source(&self) -> (Participate, bool) {
+        match self.sale.participants.get(&msg::source()) {
+            Some(participation) => (*participation, false),
+            None => (Participate {
+                participated_datetime: exec::block_timestamp(),
+                ..Default::default()
+            }, true),
+        }
+    }
+
+    fn is_fcfs_round(&self) -> bool {
+        self.fcfs.end_datetime != 0 &&
+        exec::block_timestamp() >= self.fcfs.start_datetime &&
+        exec::block_timestamp() <= self.fcfs.end_datetime
+    }
+
+    /// End of the last round, the FCFS one if it's set.
+    fn end_datetime(&self) -> u64 {
+        self.sale.end_datetime.max(self.fcfs.end_datetime)
+    }
+
+    /// Phase the sale is in at the current block. Terminal phases stick once reached,
+    /// the rest follow from the schedule and the sale results.
+    pub fn phase(&self) -> SalePhase {
+        if matches!(self.phase, SalePhase::Finalized | SalePhase::Cancelled | SalePhase::Failed) {
+            return self.phase;
+        }
+
+        // The schedule doesn't move on while the sale is paused.
+        let now = self.paused_at.unwrap_or_else(exec::block_timestamp);
+
+        if !self.is_created || self.registration.end_datetime == 0 || self.sale.end_datetime == 0 {
+            SalePhase::Draft
+        } else if now < self.registration.start_datetime {
+            SalePhase::Configured
+        } else if now <= self.registration.end_datetime {
+            SalePhase::Registration
+        } else if now < self.sale.start_datetime {
+            SalePhase::RegistrationEnded
+        } else if now <= self.end_datetime() {
+            SalePhase::Sale
+        } else if self.tokens_raised < self.soft_cap {
+            SalePhase::Failed
+        } else if self.needs_settlement() && !self.settled {
+            SalePhase::Ended
+        } else {
+            SalePhase::Finalized
+        }
+    }
+
+    /// Moves the stored phase up to the current one, one transition at a time,
+    /// so every phase passed is announced.
+    pub fn sync_phase(&mut self) -> Result<(), SaleError> {
+        let target = self.phase();
+
+        while self.phase != target {
+            let next = match self.phase {
+                SalePhase::Draft => SalePhase::Configured,
+                SalePhase::Configured => SalePhase::Registration,
+                SalePhase::Registration => SalePhase::RegistrationEnded,
+                SalePhase::RegistrationEnded => SalePhase::Sale,
+                SalePhase::Sale => SalePhase::Ended,
+                _ => target,
+            };
+
+            self.transition(next)?;
+        }
+
+        Ok(())
+    }
+
+    /// The only place the stored phase changes.
+    fn transition(&mut self, to: SalePhase) -> Result<(), SaleError> {
+        let now = exec::block_timestamp();
+
+        let event = match (self.phase, to) {
+            (SalePhase::Draft, SalePhase::Configured) => SaleEvent::SaleConfigured(now),
+            (SalePhase::Configured, SalePhase::Registration) => SaleEvent::RegistrationStarted(now),
+            (SalePhase::Registration, SalePhase::RegistrationEnded) => SaleEvent::RegistrationEnded(now),
+            (SalePhase::RegistrationEnded, SalePhase::Sale) => SaleEvent::SaleStarted(now),
+            (SalePhase::Sale, SalePhase::Ended) => SaleEvent::SaleEnded(now),
+            (SalePhase::Ended, SalePhase::Finalized) => SaleEvent::SaleFinalized(now),
+            (SalePhase::Ended, SalePhase::Failed) => SaleEvent::SaleFailed(now),
+            (
+                SalePhase::Draft
+                | SalePhase::Configured
+                | SalePhase::Registration
+                | SalePhase::RegistrationEnded
+                | SalePhase::Sale
+                | SalePhase::Ended,
+                SalePhase::Cancelled,
+            ) => SaleEvent::SaleCancelled(now),
+            _ => return Err(SaleError::InvalidPhaseTransition),
+        };
+
+        self.phase = to;
+
+        if to == SalePhase::Sale {
+            self.sale.total_weight = self.registration.total_weight;
+        }
+
+        // Sent to the admin rather than to whoever happened to trigger the sync.
+        msg::send(self.admin, event, 0).unwrap();
+
+        Ok(())
     }
 
-    fn only_admin(&self) {
-        require!(self.admin == msg::source(), "Allows only admin address");
+    /// Every non-zero share of the earnings: the fee recipients in `fee_shares` order, then
+    /// the owner as `None`. The position in that order is the payout slot.
+    fn earnings_payouts(&self) -> Vec<(u32, Option<ActorId>, Currency, u128)> {
+        let shares = self.fee_shares();
+        let mut payouts = Vec::new();
+
+        for (currency, amount) in self.raised_by_currency() {
+            let mut left = amount;
+            for (slot, (recipient, share_bps)) in shares.iter().enumerate() {
+                let split = mul_div(amount, *share_bps as u128, BPS_DENOMINATOR);
+                left -= split;
+                payouts.push((slot as u32, Some(*recipient), currency, split));
+            }
+            payouts.push((shares.len() as u32, None, currency, left));
+        }
+
+        payouts.retain(|(_, _, _, amount)| *amount > 0);
+        payouts
+    }
+
+    /// Launchpad treasury followed by the extra recipients, with their shares in basis points.
+    fn fee_shares(&self) -> Vec<(ActorId, u16)> {
+        match &self.platform_fee {
+            Some(fee) => core::iter::once((fee.treasury, fee.fee_bps))
+                .chain(fee.recipients.iter().copied())
+                .collect(),
+            None => Vec::new(),
+        }
     }
 
-    fn only_sale_owner(&self) {
-        require!(self.owner == msg::source(), "Allows only sale owner address");
+    fn participation_to_claim(&self) -> Result<Participate, SaleError> {
+        self.only_in_phase(&[SalePhase::Finalized])?;
+
+        let participant = self.sale.participants.get(&msg::source());
+        require!(participant.is_some(), SaleError::NotParticipant);
+
+        let participation = *participant.unwrap();
+        require!(participation.amount_bought > 0, SaleError::NothingToWithdraw);
+
+        Ok(participation)
+    }
+
+    /// Records the claim before the transfer, so the same tokens can't be claimed
+    /// again while the transfer is awaited.
+    async fn transfer_claimed(&mut self, amount: u128) -> Result<(), SaleError> {
+        self.sale.participants
+            .entry(msg::source())
+            .and_modify(|participation| participation.amount_claimed += amount);
+
+        let transferred = try_transfer_tokens(
+            &self.token, 
+            &exec::program_id(), 
+            &msg::source(), 
+            amount)
+            .await;
+
+        if !transferred {
+            self.sale.participants
+                .entry(msg::source())
+                .and_modify(|participation| participation.amount_claimed -= amount);
+        }
+        require!(transferred, SaleError::TransferFailed);
+
+        Ok(())
     }
 
-    fn only_if_gate_open(&self) {
-        require!(!self.gate_closed, "Gate must not be closed");
+    /// Amount of bought tokens unlocked at the current block, including already claimed ones.
+    fn vested_amount(&self, participation: &Participate) -> u128 {
+        let now = exec::block_timestamp();
+        let total = participation.amount_bought;
+
+        let vesting = match self.vesting {
+            Some(vesting) => vesting,
+            None => return total,
+        };
+
+        let tge_unlocked = mul_div(total, vesting.tge_unlock_percent as u128, 100);
+        let vesting_start = self.end_datetime().saturating_add(vesting.cliff_duration);
+
+        if now < vesting_start {
+            return tge_unlocked;
+        }
+
+        let elapsed = now - vesting_start;
+        if elapsed >= vesting.vesting_duration {
+            return total;
+        }
+
+        let linear_part = total - tge_unlocked;
+        tge_unlocked + mul_div(linear_part, elapsed as u128, vesting.vesting_duration as u128)
+    }
+
+    fn only_admin(&self) -> Result<(), SaleError> {
+        require!(self.admin == msg::source(), SaleError::NotAdmin);
+
+        Ok(())
+    }
+
+    fn only_role(&self, role: Role) -> Result<(), SaleError> {
+        require!(
+            self.admin == msg::source() || self.roles.contains(&(role, msg::source())),
+            SaleError::MissingRole(role)
+        );
+
+        Ok(())
+    }
+
+    fn only_sale_owner(&self) -> Result<(), SaleError> {
+        require!(self.owner == msg::source(), SaleError::NotSaleOwner);
+
+        Ok(())
+    }
+
+    fn only_if_gate_open(&self) -> Result<(), SaleError> {
+        require!(!self.gate_closed, SaleError::GateClosed);
+
+        Ok(())
+    }
+
+    fn only_if_not_paused(&self) -> Result<(), SaleError> {
+        require!(self.paused_at.is_none(), SaleError::Paused);
+
+        Ok(())
+    }
+
+    fn only_in_phase(&self, phases: &[SalePhase]) -> Result<(), SaleError> {
+        require!(phases.contains(&self.phase), SaleError::WrongPhase(self.phase));
+
+        Ok(())
+    }
+
+    async fn stake_of(&self, who: ActorId) -> u128 {
+        let reply: StakingEvent = msg::send_for_reply_as::<_, StakingEvent>(
+            self.staking,
+            StakingAction::StakeOf(who),
+            0,
+        )
+        .unwrap()
+        .await
+        .expect("Function call error");
+
+        if let StakingEvent::Staked(balance) = reply { balance } else { panic!("Error while parsing event") }
+    }
+
+    async fn token_unit(&self) -> u128 {
+        let reply: FTEvent = msg::send_for_reply_as::<_, FTEvent>(
+            self.token,
+            FTAction::Decimals,
+            0,
+        )
+        .unwrap()
+        .await
+        .expect("Function call error");
+
+        let decimals = if let FTEvent::Decimals(decimals) = reply { decimals } else { panic!("Error while parsing event") } as u32;
+
+        10_u128.pow(decimals)
     }
 }
 
@@ -399,4 +1633,25 @@ async fn transfer_tokens(
     .expect("Polkapad Sale: error in sending message")
     .await
     .expect("Polkapd Sale: error in transfer");
-}
\ No newline at end of file
+}
+
+/// Same as `transfer_tokens`, but reports a failed transfer instead of panicking.
+async fn try_transfer_tokens(
+    token_address: &ActorId,
+    from: &ActorId,
+    to: &ActorId,
+    amount: u128,
+) -> bool {
+    msg::send_for_reply(
+        *token_address,
+        FTAction::TransferFrom {
+            from: *from,
+            to: *to,
+            amount,
+        },
+        0,
+    )
+    .expect("Polkapad Sale: error in sending message")
+    .await
+    .is_ok()
+}
@@ -1,8 +1,52 @@
+use primitive_types::U256;
+use sha2::{Digest, Sha256};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
 #[macro_export]
 macro_rules! require {
-    ($condition: expr, $message: expr) => {
+    ($condition: expr, $error: expr) => {
         {
-            if !$condition { panic!($message); }
+            if !$condition { return Err($error); }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Computes `a * b / c` without intermediate overflow, rounding down.
+pub fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    (U256::from(a) * U256::from(b) / U256::from(c)).as_u128()
+}
+
+/// Computes `a * b / c` without intermediate overflow, rounding up.
+pub fn mul_div_ceil(a: u128, b: u128, c: u128) -> u128 {
+    let (quotient, remainder) = (U256::from(a) * U256::from(b)).div_mod(U256::from(c));
+    let quotient = if remainder.is_zero() { quotient } else { quotient + 1 };
+
+    quotient.as_u128()
+}
+
+pub fn verify_ed25519(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    match VerifyingKey::from_bytes(public_key) {
+        Ok(key) => key.verify(message, &Signature::from_bytes(signature)).is_ok(),
+        Err(_) => false,
+    }
+}
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Checks that `leaf` is in the tree with `root`. Pairs are hashed sorted,
+/// so the proof doesn't have to say on which side each sibling is.
+pub fn verify_merkle_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(leaf, |hash, sibling| {
+        let (left, right) = if hash <= *sibling { (hash, *sibling) } else { (*sibling, hash) };
+
+        Sha256::new()
+            .chain_update(left)
+            .chain_update(right)
+            .finalize()
+            .into()
+    });
+
+    computed == *root
+}
@@ -0,0 +1,103 @@
+use gstd::{ActorId, BTreeMap, Encode};
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn set_max_allocation_sizes_with_unregistered_users_should_reject_them() {
+    let system = System::new();
+    init(&system);
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, 1000);
+
+    let mut allocations = BTreeMap::new();
+    allocations.insert(ALICE.into(), 100);
+    allocations.insert(BOB.into(), 200);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::MaxAllocationSizesSet(AllocationUploadSummary {
+        chunk: None,
+        applied: vec![AllocationChange { user: ALICE.into(), previous: 0, new: 100 }],
+        rejected: vec![BOB.into()],
+    }).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetAllocationSizeOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationSize(100).encode())));
+}
+
+#[test]
+fn upload_allocation_chunk_twice_should_apply_it_once() {
+    let system = System::new();
+    init(&system);
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, 1000);
+
+    let mut allocations: BTreeMap<ActorId, u128> = BTreeMap::new();
+    allocations.insert(ALICE.into(), 100);
+
+    let chunk = AllocationChunk {
+        upload_id: 1,
+        index: 0,
+        mode: AllocationMode::Increase,
+        allocations,
+    };
+
+    let result = sale.send(SALE_ADMIN, SaleAction::UploadAllocationChunk(chunk.clone()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::MaxAllocationSizesSet(AllocationUploadSummary {
+        chunk: Some((1, 0)),
+        applied: vec![AllocationChange { user: ALICE.into(), previous: 0, new: 100 }],
+        rejected: vec![],
+    }).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::UploadAllocationChunk(chunk));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::ChunkAlreadyUploaded).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetAllocationSizeOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationSize(100).encode())));
+}
+
+#[test]
+fn set_max_allocation_sizes_should_apply_mode() {
+    let system = System::new();
+    init(&system);
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, 1000);
+
+    let mut allocations = BTreeMap::new();
+    allocations.insert(ALICE.into(), 100);
+
+    sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations.clone()));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations.clone()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::MaxAllocationSizesSet(AllocationUploadSummary {
+        applied: vec![AllocationChange { user: ALICE.into(), previous: 100, new: 100 }],
+        ..Default::default()
+    }).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Increase, allocations.clone()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::MaxAllocationSizesSet(AllocationUploadSummary {
+        applied: vec![AllocationChange { user: ALICE.into(), previous: 100, new: 200 }],
+        ..Default::default()
+    }).encode())));
+
+    let mut allocations = BTreeMap::new();
+    allocations.insert(ALICE.into(), 150);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Decrease, allocations));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::MaxAllocationSizesSet(AllocationUploadSummary {
+        applied: vec![AllocationChange { user: ALICE.into(), previous: 200, new: 50 }],
+        ..Default::default()
+    }).encode())));
+}
@@ -0,0 +1,113 @@
+use ft_io::{FTAction, FTEvent};
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn cancel_sale_should_refund_participants_registrants_and_owner() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, 100, 50);
+    prepare_user_for_registration(&system, BOB, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let token = system.get_program(SALE_TOKEN_ADDRESS);
+
+    schedule_sale(&system, &sale, sale_parameters(registration_fee_gear));
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    system.spend_blocks(1);
+
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::CancelSale);
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::SaleCancelled(system.block_timestamp()).encode())));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    assert!(result.contains(&(ALICE, SaleEvent::RefundClaimed(ALICE.into(), to_participate).encode())));
+
+    let result = sale.send(BOB, SaleAction::ClaimRefund);
+    assert!(result.contains(&(BOB, SaleEvent::RefundClaimed(BOB.into(), registration_fee_gear).encode())));
+
+    let result = sale.send(BOB, SaleAction::ClaimRefund);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::AlreadyRefunded).encode())));
+
+    sale.send(SALE_OWNER, SaleAction::WithdrawLeftover);
+
+    let result = token.send(SALE_OWNER, FTAction::BalanceOf(SALE_OWNER.into()));
+    assert!(result.contains(&(SALE_OWNER, FTEvent::Balance(TOKENS_TO_SELL).encode())));
+}
+
+#[test]
+fn participate_in_cancelled_sale_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, 5 * 10e16 as u128);
+
+    sale.send(SALE_ADMIN, SaleAction::CancelSale);
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, 5 * 10e18 as u128);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Cancelled)).encode())));
+}
+
+#[test]
+fn cancel_sale_not_by_admin_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, 1000);
+
+    let result = sale.send(ALICE, SaleAction::CancelSale);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NotAdmin).encode())));
+}
+
+#[test]
+fn cancel_sale_after_registration_fees_withdrawn_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    prepare_user_for_registration(&system, BOB, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+
+    schedule_sale(&system, &sale, sale_parameters(registration_fee_gear));
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    system.spend_blocks(1);
+
+    set_max_allocation_size_to_user(&system, ALICE, 5 * 10e16 as u128);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::WithdrawRegistrationFees);
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::RegistrationFeeWithdrawn(registration_fee_gear).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::CancelSale);
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Ended)).encode())));
+
+    let result = sale.send(BOB, SaleAction::ClaimRefund);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Ended)).encode())));
+}
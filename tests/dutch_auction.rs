@@ -0,0 +1,149 @@
+use gstd::Encode;
+use gtest::{Program, System};
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+fn configure_auction(system: &System, sale: &Program, registration_fee_gear: u128, uniform_clearing: bool) {
+    schedule_sale(system, sale, sale_parameters(registration_fee_gear));
+
+    let auction = DutchAuction {
+        start_price: 2 * TOKEN_PRICE_IN_GEAR,
+        floor_price: TOKEN_PRICE_IN_GEAR,
+        decay: PriceDecay::Steps(2),
+        uniform_clearing,
+    };
+    let result = sale.send(SALE_ADMIN, SaleAction::SetDutchAuction(auction));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::DutchAuctionSet(auction).encode())));
+
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    system.spend_blocks(1);
+}
+
+#[test]
+fn participate_in_auction_should_charge_current_price() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    prepare_user_for_registration(&system, BOB, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_auction(&system, &sale, registration_fee_gear, false);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    set_max_allocation_size_to_user(&system, BOB, tokens_bought);
+
+    // The first step is twice the floor price.
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought / 2, to_participate, 0).encode())));
+
+    // Halfway through the sale round the price drops by half of the range.
+    system.spend_blocks(40);
+
+    let result = sale.send_with_value(BOB, SaleAction::Participate, to_participate * 3 / 4);
+    assert!(result.contains(&(BOB, SaleEvent::TokensPurchased(BOB.into(), tokens_bought / 2, to_participate * 3 / 4, 0).encode())));
+}
+
+#[test]
+fn settle_auction_with_uniform_clearing_should_refund_difference() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    prepare_user_for_registration(&system, BOB, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_auction(&system, &sale, registration_fee_gear, true);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    set_max_allocation_size_to_user(&system, BOB, tokens_bought);
+
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(40);
+    sale.send_with_value(BOB, SaleAction::Participate, to_participate * 3 / 4);
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::ClaimAllocation(tokens_bought / 2));
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Ended)).encode())));
+
+    let result = sale.send(ALICE, SaleAction::Settle);
+    assert!(result.contains(&(ALICE, SaleEvent::SaleSettled(tokens_bought, to_participate * 3 / 2).encode())));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    assert!(result.contains(&(ALICE, SaleEvent::RefundClaimed(ALICE.into(), to_participate / 4).encode())));
+
+    let result = sale.send(BOB, SaleAction::ClaimRefund);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::NothingToRefund).encode())));
+}
+
+#[test]
+fn set_dutch_auction_with_floor_above_start_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(1000));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetDutchAuction(DutchAuction {
+        start_price: TOKEN_PRICE_IN_GEAR,
+        floor_price: 2 * TOKEN_PRICE_IN_GEAR,
+        decay: PriceDecay::Linear,
+        uniform_clearing: false,
+    }));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::InvalidDutchAuction).encode())));
+}
+
+#[test]
+fn settle_auction_below_soft_cap_after_clearing_should_fail_sale() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    prepare_user_for_registration(&system, BOB, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, SaleParameters {
+        // Reached at the prices paid, but not at the clearing price.
+        soft_cap: to_participate * 3 / 2 + 1,
+        ..sale_parameters(registration_fee_gear)
+    });
+    sale.send(SALE_ADMIN, SaleAction::SetDutchAuction(DutchAuction {
+        start_price: 2 * TOKEN_PRICE_IN_GEAR,
+        floor_price: TOKEN_PRICE_IN_GEAR,
+        decay: PriceDecay::Steps(2),
+        uniform_clearing: true,
+    }));
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    system.spend_blocks(1);
+
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    set_max_allocation_size_to_user(&system, BOB, tokens_bought);
+
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(40);
+    sale.send_with_value(BOB, SaleAction::Participate, to_participate * 3 / 4);
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::Settle);
+    assert!(result.contains(&(ALICE, SaleEvent::SaleFailed(system.block_timestamp()).encode())));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    assert!(result.contains(&(ALICE, SaleEvent::RefundClaimed(ALICE.into(), to_participate).encode())));
+}
@@ -0,0 +1,95 @@
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+fn configure_sale_with_fcfs_round(system: &System, registration_fee_gear: u128, open_to_all: bool, max_allocation: u128) {
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(system, &sale, sale_parameters(registration_fee_gear));
+
+    let sale_end_date = system.block_timestamp() + 1 + 80000;
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetFcfsRound(FcfsParameters {
+        start_datetime: sale_end_date + 1,
+        end_datetime: sale_end_date + 10_000_000,
+        max_allocation,
+        open_to_all,
+    }));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::FcfsRoundSet(system.block_timestamp()).encode())));
+
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+}
+
+#[test]
+fn participate_in_fcfs_round_should_participated_up_to_cap() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = to_participate * 10_u128.pow(DECIMALS) / TOKEN_PRICE_IN_GEAR;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale_with_fcfs_round(&system, registration_fee_gear, false, tokens_bought);
+
+    system.spend_blocks(100);
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_participate, 0).encode())));
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::AllocationExceeded).encode())));
+}
+
+#[test]
+fn participate_in_fcfs_round_when_not_registered_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = to_participate * 10_u128.pow(DECIMALS) / TOKEN_PRICE_IN_GEAR;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    system.mint_to(BOB, 100 * 10e18 as u128);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale_with_fcfs_round(&system, registration_fee_gear, false, tokens_bought);
+
+    system.spend_blocks(100);
+
+    let result = sale.send_with_value(BOB, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::NotRegistered).encode())));
+}
+
+#[test]
+fn participate_in_open_fcfs_round_when_not_registered_should_participated() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = to_participate * 10_u128.pow(DECIMALS) / TOKEN_PRICE_IN_GEAR;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    system.mint_to(BOB, 100 * 10e18 as u128);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale_with_fcfs_round(&system, registration_fee_gear, true, tokens_bought);
+
+    system.spend_blocks(100);
+
+    let result = sale.send_with_value(BOB, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(BOB, SaleEvent::TokensPurchased(BOB.into(), tokens_bought, to_participate, 0).encode())));
+
+    let result = sale.send(BOB, SaleAction::GetTotalSold);
+    assert!(result.contains(&(BOB, SaleEvent::TotalSold(tokens_bought).encode())));
+}
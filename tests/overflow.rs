@@ -0,0 +1,112 @@
+use ft_io::{FTAction, FTEvent};
+use gstd::Encode;
+use gtest::{System, Log};
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn settle_oversubscribed_sale_should_split_tokens_and_refund_overflow() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let tokens_to_sell = 5 * 10e16 as u128;
+    let spent = tokens_to_sell * TOKEN_PRICE_IN_GEAR / 10_u128.pow(DECIMALS);
+    let to_commit = 2 * spent;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let token = system.get_program(SALE_TOKEN_ADDRESS);
+
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        tokens_to_sell,
+        overflow: true,
+        ..sale_parameters(registration_fee_gear)
+    });
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_commit);
+    assert!(result.contains(&(ALICE, SaleEvent::GearCommitted(ALICE.into(), to_commit).encode())));
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Ended)).encode())));
+
+    let result = sale.send(ALICE, SaleAction::Settle);
+    assert!(result.contains(&(ALICE, SaleEvent::SaleSettled(tokens_to_sell, spent).encode())));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    let mailbox = system.get_mailbox(ALICE);
+    let log = Log::builder()
+        .dest(ALICE)
+        .payload(SaleEvent::RefundClaimed(ALICE.into(), to_commit - spent));
+
+    mailbox.claim_value(log.clone());
+    assert!(result.contains(&log));
+
+    sale.send(ALICE, SaleAction::WithdrawAllocation);
+
+    let result = token.send(ALICE, FTAction::BalanceOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, FTEvent::Balance(tokens_to_sell).encode())));
+}
+
+#[test]
+fn settle_sale_twice_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_commit = 5 * 10e18 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        overflow: true,
+        ..sale_parameters(registration_fee_gear)
+    });
+
+    sale.send_with_value(ALICE, SaleAction::Participate, to_commit);
+    system.spend_blocks(5000);
+
+    sale.send(ALICE, SaleAction::Settle);
+
+    let result = sale.send(ALICE, SaleAction::Settle);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Finalized)).encode())));
+}
+
+#[test]
+fn set_allocations_in_overflow_sale_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, SaleParameters {
+        overflow: true,
+        ..sale_parameters(registration_fee_gear)
+    });
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetTiers(vec![Tier { min_stake: 10, weight: 1 }]));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::AllocationsUnusedInOverflow).encode())));
+
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+
+    let mut allocations = gstd::BTreeMap::new();
+    allocations.insert(ALICE.into(), 5 * 10e16 as u128);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::AllocationsUnusedInOverflow).encode())));
+}
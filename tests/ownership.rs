@@ -0,0 +1,54 @@
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn transfer_admin_should_transferred() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::ProposeAdmin(BOB.into()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::AdminProposed(BOB.into()).encode())));
+
+    let result = sale.send(ALICE, SaleAction::AcceptAdmin);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NotPendingAdmin).encode())));
+
+    let result = sale.send(BOB, SaleAction::AcceptAdmin);
+    assert!(result.contains(&(BOB, SaleEvent::AdminChanged(BOB.into()).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::CreateSale(sale_parameters(1000)));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::NotAdmin).encode())));
+
+    let result = sale.send(BOB, SaleAction::CreateSale(sale_parameters(1000)));
+    assert!(result.contains(&(BOB, SaleEvent::SaleCreated(sale_parameters(1000)).encode())));
+}
+
+#[test]
+fn transfer_owner_should_transferred() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    sale.send(SALE_ADMIN, SaleAction::CreateSale(sale_parameters(1000)));
+
+    let result = sale.send(ALICE, SaleAction::ProposeOwner(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NotSaleOwner).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::ProposeOwner(SALE_ADMIN.into()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::NotSaleOwner).encode())));
+
+    let result = sale.send(SALE_OWNER, SaleAction::ProposeOwner(BOB.into()));
+    assert!(result.contains(&(SALE_OWNER, SaleEvent::OwnerProposed(BOB.into()).encode())));
+
+    let result = sale.send(BOB, SaleAction::AcceptOwner);
+    assert!(result.contains(&(BOB, SaleEvent::OwnerChanged(BOB.into()).encode())));
+
+    let result = sale.send(SALE_OWNER, SaleAction::DepositTokens);
+    assert!(result.contains(&(SALE_OWNER, SaleEvent::Error(SaleError::NotSaleOwner).encode())));
+}
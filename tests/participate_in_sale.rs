@@ -41,12 +41,91 @@ fn participate_in_sale_should_participated() {
     assert!(result.contains(&(ALICE, SaleEvent::Participation(Participate {
         amount_bought: total_user_ftr_amount,
         amount_paid_gear: to_participate,
-        participated_datetime: system.block_timestamp()
+        currency: Currency::Gear,
+        amount_claimed: 0,
+        participated_datetime: system.block_timestamp(),
+        overflow_refund_gear: 0,
+        refunded: false,
     }).encode())));
 
     let result = sale.send(ALICE, SaleAction::GetTotalRaised);
-    assert!(result.contains(&(ALICE, SaleEvent::TotalRaised(to_participate).encode())));
+    assert!(result.contains(&(ALICE, SaleEvent::TotalRaised(vec![(Currency::Gear, to_participate)]).encode())));
 
     let result = sale.send(ALICE, SaleAction::GetTotalSold);
     assert!(result.contains(&(ALICE, SaleEvent::TotalSold(total_user_ftr_amount).encode())));
-}
\ No newline at end of file
+}
+
+#[test]
+fn participate_in_sale_several_times_should_sum_up_purchases() {
+    let system = System::new();
+    init(&system);
+    
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128; 
+    let tokens_per_purchase = to_participate * 10_u128.pow(DECIMALS) / TOKEN_PRICE_IN_GEAR;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+
+    set_max_allocation_size_to_user(&system, ALICE, 2 * tokens_per_purchase);
+
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    let participated_datetime = system.block_timestamp();
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_per_purchase, to_participate, 0).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetParticipationOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::Participation(Participate {
+        amount_bought: 2 * tokens_per_purchase,
+        amount_paid_gear: 2 * to_participate,
+        currency: Currency::Gear,
+        amount_claimed: 0,
+        participated_datetime,
+        overflow_refund_gear: 0,
+        refunded: false,
+    }).encode())));
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::AllocationExceeded).encode())));
+}
+
+#[test]
+fn participate_in_sale_with_value_not_multiple_of_price_should_return_change() {
+    let system = System::new();
+    init(&system);
+    
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_charge = 5 * 10e18 as u128; 
+    let change = 57;
+    let tokens_bought = to_charge * 10_u128.pow(DECIMALS) / TOKEN_PRICE_IN_GEAR;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_charge + change);
+    let mailbox = system.get_mailbox(ALICE);
+    let log = Log::builder()
+        .dest(ALICE)
+        .payload(SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_charge, change));
+
+    mailbox.claim_value(log.clone());
+
+    assert!(result.contains(&log));
+    assert_eq!(sale.balance(), to_charge);
+
+    let result = sale.send(ALICE, SaleAction::GetTotalRaised);
+    assert!(result.contains(&(ALICE, SaleEvent::TotalRaised(vec![(Currency::Gear, to_charge)]).encode())));
+}
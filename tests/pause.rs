@@ -0,0 +1,77 @@
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn participate_while_paused_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::Pause);
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Paused(system.block_timestamp()).encode())));
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::Paused).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::Unpause(false));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Unpaused(system.block_timestamp(), 0).encode())));
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_participate, 0).encode())));
+}
+
+#[test]
+fn unpause_with_extension_should_extend_sale_round() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+
+    sale.send(SALE_ADMIN, SaleAction::Pause);
+    let paused_at = system.block_timestamp();
+
+    // Long enough for the sale round to be over without the extension.
+    system.spend_blocks(100);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::Unpause(true));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Unpaused(
+        system.block_timestamp(),
+        system.block_timestamp() - paused_at,
+    ).encode())));
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_participate, 0).encode())));
+}
+
+#[test]
+fn unpause_when_not_paused_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::Unpause(false));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::NotPaused).encode())));
+}
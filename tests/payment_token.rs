@@ -0,0 +1,109 @@
+use ft_io::{FTAction, FTEvent};
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+fn token_sale_parameters(registration_fee_gear: u128) -> SaleParameters {
+    SaleParameters {
+        payment_token: Some(PAYMENT_TOKEN_ADDRESS.into()),
+        ..sale_parameters(registration_fee_gear)
+    }
+}
+
+#[test]
+fn participate_with_payment_token_should_participated() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+    transfer_tokens(&system, PAYMENT_TOKEN_ADDRESS, DEPLOYER, ALICE, to_participate);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let payment_token = system.get_program(PAYMENT_TOKEN_ADDRESS);
+
+    configure_sale_with_parameters(&system, &sale, token_sale_parameters(registration_fee_gear));
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+
+    let result = sale.send(ALICE, SaleAction::ParticipateWithTokens(PAYMENT_TOKEN_ADDRESS.into(), to_participate, None));
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_participate, 0).encode())));
+
+    let result = payment_token.send(ALICE, FTAction::BalanceOf(SALE_ADDRESS.into()));
+    assert!(result.contains(&(ALICE, FTEvent::Balance(to_participate).encode())));
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(SALE_OWNER, SaleAction::WithdrawEarnings);
+    assert!(result.contains(&(SALE_OWNER, SaleEvent::EarningsWithdrawn(vec![(Currency::Token(PAYMENT_TOKEN_ADDRESS.into()), to_participate)]).encode())));
+
+    let result = payment_token.send(SALE_OWNER, FTAction::BalanceOf(SALE_OWNER.into()));
+    assert!(result.contains(&(SALE_OWNER, FTEvent::Balance(TOKENS_TO_SELL + to_participate).encode())));
+}
+
+#[test]
+fn participate_with_gear_in_token_sale_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale_with_parameters(&system, &sale, token_sale_parameters(registration_fee_gear));
+    set_max_allocation_size_to_user(&system, ALICE, 5 * 10e16 as u128);
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::CurrencyNotAccepted).encode())));
+}
+
+#[test]
+fn participate_in_several_currencies_should_book_each_separately() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    prepare_user_for_registration(&system, BOB, 100 * 10e18 as u128, 100, 50);
+    transfer_tokens(&system, PAYMENT_TOKEN_ADDRESS, DEPLOYER, BOB, to_participate / 2);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(registration_fee_gear));
+
+    // One sale token costs half as many payment tokens as GEAR.
+    let payment_token = Currency::Token(PAYMENT_TOKEN_ADDRESS.into());
+    let result = sale.send(SALE_ADMIN, SaleAction::AddPaymentCurrency(payment_token, TOKEN_PRICE_IN_GEAR / 2));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::PaymentCurrencyAdded(payment_token, TOKEN_PRICE_IN_GEAR / 2).encode())));
+
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    system.spend_blocks(1);
+
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    set_max_allocation_size_to_user(&system, BOB, tokens_bought);
+
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+
+    let result = sale.send(BOB, SaleAction::ParticipateWithTokens(PAYMENT_TOKEN_ADDRESS.into(), to_participate / 2, None));
+    assert!(result.contains(&(BOB, SaleEvent::TokensPurchased(BOB.into(), tokens_bought, to_participate / 2, 0).encode())));
+
+    let result = sale.send_with_value(BOB, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::CurrencyMismatch).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetTotalRaised);
+    assert!(result.contains(&(ALICE, SaleEvent::TotalRaised(vec![
+        (Currency::Gear, to_participate),
+        (payment_token, to_participate / 2),
+    ]).encode())));
+}
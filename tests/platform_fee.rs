@@ -0,0 +1,101 @@
+use gstd::Encode;
+use gtest::{Log, System};
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+fn platform_fee(fee_bps: u16, recipients: Vec<(u64, u16)>) -> PlatformFee {
+    PlatformFee {
+        fee_bps,
+        treasury: DEPLOYER.into(),
+        recipients: recipients.into_iter().map(|(recipient, share_bps)| (recipient.into(), share_bps)).collect(),
+    }
+}
+
+#[test]
+fn withdraw_earnings_should_split_platform_fee() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let treasury_split = to_participate * 5 / 100;
+    let bob_split = to_participate / 100;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(registration_fee_gear));
+
+    let fee = platform_fee(500, vec![(BOB, 100)]);
+    let result = sale.send(SALE_ADMIN, SaleAction::SetPlatformFee(fee.clone()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::PlatformFeeSet(fee).encode())));
+
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    system.spend_blocks(1);
+
+    set_max_allocation_size_to_user(&system, ALICE, 5 * 10e16 as u128);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(SALE_OWNER, SaleAction::WithdrawEarnings);
+    let owner_share = to_participate - treasury_split - bob_split;
+    assert!(result.contains(&(SALE_OWNER, SaleEvent::EarningsWithdrawn(vec![(Currency::Gear, owner_share)]).encode())));
+
+    let treasury_log = Log::builder()
+        .dest(DEPLOYER)
+        .payload(SaleEvent::EarningsSplit(DEPLOYER.into(), Currency::Gear, treasury_split));
+    assert!(result.contains(&treasury_log));
+
+    let bob_log = Log::builder()
+        .dest(BOB)
+        .payload(SaleEvent::EarningsSplit(BOB.into(), Currency::Gear, bob_split));
+    assert!(result.contains(&bob_log));
+
+    system.get_mailbox(BOB).claim_value(bob_log);
+    assert!(system.balance_of(BOB) == bob_split);
+
+    let result = sale.send(SALE_OWNER, SaleAction::WithdrawEarnings);
+    assert!(result.contains(&(SALE_OWNER, SaleEvent::Error(SaleError::EarningsAlreadyWithdrawn).encode())));
+}
+
+#[test]
+fn set_platform_fee_over_full_share_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(1000));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetPlatformFee(platform_fee(9000, vec![(BOB, 1001)])));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::InvalidPlatformFee).encode())));
+}
+
+#[test]
+fn set_platform_fee_after_sale_started_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    prepare_user_for_registration(&system, ALICE, 1000, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, 1000);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetPlatformFee(platform_fee(500, vec![])));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Sale)).encode())));
+}
+
+#[test]
+fn set_platform_fee_not_by_admin_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(1000));
+
+    let result = sale.send(SALE_OWNER, SaleAction::SetPlatformFee(platform_fee(500, vec![])));
+    assert!(result.contains(&(SALE_OWNER, SaleEvent::Error(SaleError::NotAdmin).encode())));
+}
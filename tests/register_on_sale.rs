@@ -23,6 +23,7 @@ fn register_on_sale_should_registered() {
         tokens_to_sell: 100,
         token_price_in_gear: 5,   
         registration_fee_gear: 1000,
+        ..Default::default()
     }));
 
     let register_start_date = system.block_timestamp();
@@ -53,6 +54,7 @@ fn register_on_sale_when_no_stakes_should_failed() {
         tokens_to_sell: 100,
         token_price_in_gear: 5,   
         registration_fee_gear: 1000,
+        ..Default::default()
     }));
 
     let register_start_date = system.block_timestamp();
@@ -62,7 +64,7 @@ fn register_on_sale_when_no_stakes_should_failed() {
     sale.send(SALE_ADMIN, SaleAction::SetRegistrationTime(register_start_date, register_end_date));
 
     let result = sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
-    assert!(result.main_failed());
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NoStake).encode())));
 }
 
 #[test]
@@ -81,6 +83,7 @@ fn register_on_sale_when_not_enough_fee_deposited_should_failed() {
         tokens_to_sell: 100,
         token_price_in_gear: 5,   
         registration_fee_gear: 1000,
+        ..Default::default()
     }));
 
     let register_start_date = system.block_timestamp();
@@ -90,7 +93,7 @@ fn register_on_sale_when_not_enough_fee_deposited_should_failed() {
     sale.send(SALE_ADMIN, SaleAction::SetRegistrationTime(register_start_date, register_end_date));
 
     let result = sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
-    assert!(result.main_failed());
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::InvalidRegistrationFee).encode())));
 }
 
 #[test]
@@ -109,6 +112,7 @@ fn register_on_sale_when_user_already_registered_should_failed() {
         tokens_to_sell: 100,
         token_price_in_gear: 5,   
         registration_fee_gear: 500,
+        ..Default::default()
     }));
 
     let register_start_date = system.block_timestamp();
@@ -119,5 +123,71 @@ fn register_on_sale_when_user_already_registered_should_failed() {
 
     sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
     let result = sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
-    assert!(result.main_failed());
-}
\ No newline at end of file
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::AlreadyRegistered).encode())));
+}
+
+#[test]
+fn unregister_should_refund_registration_fee() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(registration_fee_gear));
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+
+    let result = sale.send(ALICE, SaleAction::Unregister);
+    let log = Log::builder()
+        .dest(ALICE)
+        .payload(SaleEvent::UserUnregistered(ALICE.into(), registration_fee_gear));
+
+    system.get_mailbox(ALICE).claim_value(log.clone());
+
+    assert!(result.contains(&log));
+    assert_eq!(system.balance_of(SALE_ADDRESS), 0);
+    assert_eq!(system.balance_of(ALICE), registration_fee_gear);
+
+    let result = sale.send(ALICE, SaleAction::GetAllocationSizeOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NotRegistered).encode())));
+}
+
+#[test]
+fn unregister_after_registration_round_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+
+    let result = sale.send(ALICE, SaleAction::Unregister);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Sale)).encode())));
+}
+
+#[test]
+fn remove_registered_not_by_admin_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+    prepare_user_for_registration(&system, BOB, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(registration_fee_gear));
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+
+    let result = sale.send(BOB, SaleAction::RemoveRegistered(ALICE.into()));
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::NotAdmin).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::RemoveRegistered(ALICE.into()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::RegisteredRemoved(ALICE.into()).encode())));
+
+    let result = sale.send(ALICE, SaleAction::Unregister);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NotRegistered).encode())));
+}
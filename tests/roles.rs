@@ -0,0 +1,50 @@
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn set_max_allocation_sizes_by_allocator_should_set() {
+    let system = System::new();
+    init(&system);
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, 1000);
+
+    let mut allocations = gstd::BTreeMap::new();
+    allocations.insert(ALICE.into(), 100);
+
+    let result = sale.send(BOB, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations.clone()));
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::MissingRole(Role::Allocator)).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::GrantRole(Role::Allocator, BOB.into()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::RoleGranted(Role::Allocator, BOB.into()).encode())));
+
+    let result = sale.send(BOB, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations.clone()));
+    assert!(result.contains(&(BOB, SaleEvent::MaxAllocationSizesSet(AllocationUploadSummary {
+        applied: vec![AllocationChange { user: ALICE.into(), previous: 0, new: 100 }],
+        ..Default::default()
+    }).encode())));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::RevokeRole(Role::Allocator, BOB.into()));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::RoleRevoked(Role::Allocator, BOB.into()).encode())));
+
+    let result = sale.send(BOB, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations));
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::MissingRole(Role::Allocator)).encode())));
+}
+
+#[test]
+fn grant_role_not_by_admin_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+
+    let result = sale.send(BOB, SaleAction::GrantRole(Role::Pauser, BOB.into()));
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::NotAdmin).encode())));
+}
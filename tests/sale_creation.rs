@@ -19,7 +19,8 @@ fn create_sale_as_admin_should_created() {
         staking: STAKING_ADDRESS.into(),
         tokens_to_sell: 100,
         registration_fee_gear: 1,
-        token_price_in_gear: TOKEN_PRICE_IN_GEAR
+        token_price_in_gear: TOKEN_PRICE_IN_GEAR,
+        ..Default::default()
     };
 
     let result = sale.send(SALE_ADMIN, SaleAction::CreateSale(parameters));
@@ -38,7 +39,7 @@ fn create_sale_as_not_admin_should_failed() {
     };
 
     let result = sale.send(ALICE, SaleAction::CreateSale(parameters));
-    assert!(result.main_failed());
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NotAdmin).encode())));
 }
 
 #[test]
@@ -54,13 +55,14 @@ fn create_sale_as_admin_when_sale_already_created_should_failed() {
         staking: STAKING_ADDRESS.into(),
         tokens_to_sell: 100,
         registration_fee_gear: 1,
-        token_price_in_gear: TOKEN_PRICE_IN_GEAR,  
+        token_price_in_gear: TOKEN_PRICE_IN_GEAR,
+        ..Default::default()
     };
 
     sale.send(SALE_ADMIN, SaleAction::CreateSale(parameters));
     
     let result = sale.send(SALE_ADMIN, SaleAction::CreateSale(parameters));
-    assert!(result.main_failed());
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::SaleAlreadyCreated).encode())));
 }
 
 #[test]
@@ -76,13 +78,14 @@ fn create_sale_as_admin_when_sale_start_date_in_past_should_failed() {
         staking: STAKING_ADDRESS.into(),
         tokens_to_sell: 100,
         registration_fee_gear: 1,
-        token_price_in_gear: TOKEN_PRICE_IN_GEAR,   
+        token_price_in_gear: TOKEN_PRICE_IN_GEAR,
+        ..Default::default()
     };
 
     sale.send(SALE_ADMIN, SaleAction::CreateSale(parameters));
     let result = sale.send(SALE_ADMIN, SaleAction::SetSaleTime(system.block_timestamp() - 2, system.block_timestamp() - 1));
     
-    assert!(result.main_failed());
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::InvalidSaleTime).encode())));
 }
 
 #[test]
@@ -98,10 +101,11 @@ fn create_sale_as_admin_when_tokens_to_sell_is_0_should_failed() {
         staking: STAKING_ADDRESS.into(),
         tokens_to_sell: 0,
         registration_fee_gear: 1,
-        token_price_in_gear: TOKEN_PRICE_IN_GEAR,   
+        token_price_in_gear: TOKEN_PRICE_IN_GEAR,
+        ..Default::default()
     };
 
     let result = sale.send(SALE_ADMIN, SaleAction::CreateSale(parameters));
-    assert!(result.main_failed());
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::ZeroTokensToSell).encode())));
 }
 
@@ -0,0 +1,106 @@
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn sale_phases_should_follow_schedule() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, sale_parameters(registration_fee_gear));
+
+    let result = sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::SaleConfigured(system.block_timestamp()).encode())));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::RegistrationStarted(system.block_timestamp()).encode())));
+
+    system.spend_blocks(1);
+
+    let mut allocations = gstd::BTreeMap::new();
+    allocations.insert(ALICE.into(), 5 * 10e16 as u128);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::RegistrationEnded(system.block_timestamp()).encode())));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::SaleStarted(system.block_timestamp()).encode())));
+
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    let result = sale.send(SALE_OWNER, SaleAction::WithdrawEarnings);
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::SaleEnded(system.block_timestamp()).encode())));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::SaleFinalized(system.block_timestamp()).encode())));
+}
+
+#[test]
+fn participate_before_sale_round_starts_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    sale.send(SALE_ADMIN, SaleAction::CreateSale(sale_parameters(registration_fee_gear)));
+    sale.send(SALE_OWNER, SaleAction::DepositTokens);
+
+    let register_end_date = system.block_timestamp() + 1;
+    sale.send(SALE_ADMIN, SaleAction::SetSaleTime(register_end_date + 5000, register_end_date + 80000));
+    sale.send(SALE_ADMIN, SaleAction::SetRegistrationTime(system.block_timestamp(), register_end_date));
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+
+    system.spend_blocks(1);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::WrongPhase(SalePhase::RegistrationEnded)).encode())));
+
+    system.spend_blocks(5);
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::SaleStarted(system.block_timestamp()).encode())));
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_participate, 0).encode())));
+}
+
+#[test]
+fn register_after_registration_round_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, BOB, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+
+    let result = sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Sale)).encode())));
+}
+
+#[test]
+fn set_sale_time_after_registration_started_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale(&system, &sale, registration_fee_gear);
+
+    let sale_start_date = system.block_timestamp() + 1000;
+    let result = sale.send(SALE_ADMIN, SaleAction::SetSaleTime(sale_start_date, sale_start_date + 1000));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Sale)).encode())));
+}
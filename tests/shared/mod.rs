@@ -10,34 +10,53 @@ pub const PLPD_TOKEN_ADDRESS: u64 = 1;
 pub const SALE_TOKEN_ADDRESS: u64 = 2;
 pub const STAKING_ADDRESS: u64 = 3;
 pub const SALE_ADDRESS: u64 = 4;
+pub const PAYMENT_TOKEN_ADDRESS: u64 = 5;
 
 pub const DEPLOYER: u64 = 10;
 pub const SALE_OWNER: u64 = 11;
 pub const SALE_ADMIN: u64 = 12;
 pub const ALICE: u64 = 13;
-// pub const BOB: u64 = 14;
+pub const BOB: u64 = 14;
 
 pub const TOKENS_TO_SELL: u128 = 100_000_000 * 10e18 as u128;
 pub const TOKEN_PRICE_IN_GEAR: u128 =  10 * 10e18 as u128;
 pub const DECIMALS: u32 =  18;
 
-pub fn configure_sale(system: &System, sale: &Program, registration_fee_gear: u128) {
-    sale.send(SALE_ADMIN, SaleAction::CreateSale(SaleParameters {
+pub fn sale_parameters(registration_fee_gear: u128) -> SaleParameters {
+    SaleParameters {
         token: SALE_TOKEN_ADDRESS.into(),
         owner: SALE_OWNER.into(),
         staking: STAKING_ADDRESS.into(),
         tokens_to_sell: TOKENS_TO_SELL,
         token_price_in_gear: TOKEN_PRICE_IN_GEAR,
         registration_fee_gear,
-    }));
+        ..Default::default()
+    }
+}
+
+pub fn configure_sale(system: &System, sale: &Program, registration_fee_gear: u128) {
+    configure_sale_with_parameters(system, sale, sale_parameters(registration_fee_gear));
+}
+
+pub fn configure_sale_with_parameters(system: &System, sale: &Program, parameters: SaleParameters) {
+    let registration_fee_gear = parameters.registration_fee_gear;
+    schedule_sale(system, sale, parameters);
+
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear.clone());
+    system.spend_blocks(1);
+}
+
+/// Creates the sale with the registration round open at the current block
+/// and the sale round right after it.
+pub fn schedule_sale(system: &System, sale: &Program, parameters: SaleParameters) {
+    sale.send(SALE_ADMIN, SaleAction::CreateSale(parameters));
 
     let register_start_date = system.block_timestamp();
     let register_end_date = system.block_timestamp() + 1;
 
     sale.send(SALE_OWNER, SaleAction::DepositTokens);
-    sale.send(SALE_ADMIN, SaleAction::SetSaleTime(register_start_date, register_end_date + 80000));
+    sale.send(SALE_ADMIN, SaleAction::SetSaleTime(register_end_date + 1, register_end_date + 80000));
     sale.send(SALE_ADMIN, SaleAction::SetRegistrationTime(register_start_date, register_end_date));
-    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear.clone());
 }
 
 pub fn prepare_user_for_registration(system: &System, user: u64, gear_amount: u128, plpd_amount: u128, to_stake: u128) {
@@ -55,7 +74,7 @@ pub fn set_max_allocation_size_to_user(system: &System, user: u64, allocation_si
     let mut allocations = BTreeMap::new();
     allocations.insert(user.into(), allocation_size);
 
-    sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(allocations));
+    sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations));
 }
 
 pub fn transfer_tokens(system: &System, token: u64, from: u64, to: u64, amount: u128) {
@@ -74,7 +93,8 @@ pub fn init(system: &System) {
     init_token(&system, "Test Polkapad", "TPLPD");
     init_token(&system, "Future", "FTR");
     init_staking(&system);
-    init_sale(&system)
+    init_sale(&system);
+    init_token(&system, "Test Stablecoin", "TUSD");
 }
 
 fn init_sale(system: &System) {
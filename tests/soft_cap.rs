@@ -0,0 +1,109 @@
+use ft_io::{FTAction, FTEvent};
+use gstd::Encode;
+use gtest::{System, Log};
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn claim_refund_when_soft_cap_not_reached_should_refunded() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        soft_cap: to_participate + 1,
+        ..sale_parameters(registration_fee_gear)
+    });
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Failed)).encode())));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    let mailbox = system.get_mailbox(ALICE);
+    let log = Log::builder()
+        .dest(ALICE)
+        .payload(SaleEvent::RefundClaimed(ALICE.into(), to_participate));
+
+    mailbox.claim_value(log.clone());
+
+    assert!(result.contains(&log));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::AlreadyRefunded).encode())));
+}
+
+#[test]
+fn withdraw_when_soft_cap_not_reached_should_return_all_tokens_to_owner() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let token = system.get_program(SALE_TOKEN_ADDRESS);
+
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        soft_cap: to_participate + 1,
+        ..sale_parameters(registration_fee_gear)
+    });
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    let result = sale.send(SALE_OWNER, SaleAction::WithdrawEarnings);
+    assert!(result.contains(&(SALE_OWNER, SaleEvent::Error(SaleError::WrongPhase(SalePhase::Failed)).encode())));
+
+    sale.send(SALE_OWNER, SaleAction::WithdrawLeftover);
+
+    let result = token.send(SALE_OWNER, FTAction::BalanceOf(SALE_OWNER.into()));
+    assert!(result.contains(&(SALE_OWNER, FTEvent::Balance(TOKENS_TO_SELL).encode())));
+}
+
+#[test]
+fn claim_refund_when_soft_cap_reached_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        soft_cap: to_participate,
+        ..sale_parameters(registration_fee_gear)
+    });
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NothingToRefund).encode())));
+}
@@ -0,0 +1,135 @@
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+use staking_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn check_stake_after_unstake_should_shrink_allocation() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let allocation = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let staking = system.get_program(STAKING_ADDRESS);
+
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, allocation);
+
+    staking.send(ALICE, StakingAction::Unstake(plpd_to_stake / 2));
+
+    let result = sale.send(BOB, SaleAction::CheckStake(ALICE.into()));
+    assert!(result.contains(&(BOB, SaleEvent::StakeChecked(ALICE.into(), plpd_to_stake / 2, allocation / 2).encode())));
+}
+
+#[test]
+fn participate_after_full_unstake_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let staking = system.get_program(STAKING_ADDRESS);
+
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, 5 * 10e16 as u128);
+
+    staking.send(ALICE, StakingAction::Unstake(plpd_to_stake));
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::AllocationExceeded).encode())));
+}
+
+#[test]
+fn unstake_after_buying_should_cut_purchase() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_to_stake = 50;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let staking = system.get_program(STAKING_ADDRESS);
+
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+
+    let result = sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_participate, 0).encode())));
+
+    staking.send(ALICE, StakingAction::Unstake(plpd_to_stake / 2));
+
+    let result = sale.send(BOB, SaleAction::CheckStake(ALICE.into()));
+    assert!(result.contains(&(BOB, SaleEvent::StakeChecked(ALICE.into(), plpd_to_stake / 2, tokens_bought / 2).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetTotalSold);
+    assert!(result.contains(&(ALICE, SaleEvent::TotalSold(tokens_bought / 2).encode())));
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationWithdrawn(ALICE.into(), tokens_bought / 2).encode())));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    assert!(result.contains(&(ALICE, SaleEvent::RefundClaimed(ALICE.into(), to_participate / 2).encode())));
+}
+
+#[test]
+fn full_unstake_later_in_sale_round_should_cut_whole_purchase() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_to_stake = 50;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let staking = system.get_program(STAKING_ADDRESS);
+
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+
+    // Still inside the sale round, which closes 80 blocks after it opens.
+    system.spend_blocks(70);
+    staking.send(ALICE, StakingAction::Unstake(plpd_to_stake));
+
+    let result = sale.send(BOB, SaleAction::CheckStake(ALICE.into()));
+    assert!(result.contains(&(BOB, SaleEvent::StakeChecked(ALICE.into(), 0, 0).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetTotalSold);
+    assert!(result.contains(&(ALICE, SaleEvent::TotalSold(0).encode())));
+
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NothingToWithdraw).encode())));
+
+    let result = sale.send(ALICE, SaleAction::ClaimRefund);
+    assert!(result.contains(&(ALICE, SaleEvent::RefundClaimed(ALICE.into(), to_participate).encode())));
+}
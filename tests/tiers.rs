@@ -0,0 +1,96 @@
+use gstd::Encode;
+use gtest::{Program, System};
+
+use sale_io::*;
+use staking_io::*;
+
+mod shared;
+use shared::*;
+
+fn configure_tiered_sale(system: &System, sale: &Program, registration_fee_gear: u128) {
+    schedule_sale(system, sale, sale_parameters(registration_fee_gear));
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetTiers(vec![
+        Tier { min_stake: 10, weight: 1 },
+        Tier { min_stake: 50, weight: 3 },
+    ]));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::TiersSet(system.block_timestamp()).encode())));
+}
+
+#[test]
+fn register_on_tiered_sale_should_assign_allocation_by_tier() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_tiered_sale(&system, &sale, registration_fee_gear);
+
+    let result = sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    assert!(result.contains(&(ALICE, SaleEvent::UserRegistered(ALICE.into()).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetAllocationSizeOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationSize(TOKENS_TO_SELL).encode())));
+}
+
+#[test]
+fn register_on_tiered_sale_when_stake_below_lowest_tier_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 5);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_tiered_sale(&system, &sale, registration_fee_gear);
+
+    let result = sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::StakeBelowLowestTier).encode())));
+}
+
+#[test]
+fn set_max_allocation_sizes_on_tiered_sale_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    configure_tiered_sale(&system, &sale, registration_fee_gear);
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+
+    let mut allocations = gstd::BTreeMap::new();
+    allocations.insert(ALICE.into(), 100);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::SetMaxAllocationSizes(AllocationMode::Set, allocations));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::AllocationsDerivedFromTiers).encode())));
+}
+
+#[test]
+fn stake_drop_during_sale_should_not_change_other_allocations() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+    prepare_user_for_registration(&system, BOB, registration_fee_gear, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let staking = system.get_program(STAKING_ADDRESS);
+
+    configure_tiered_sale(&system, &sale, registration_fee_gear);
+    sale.send_with_value(ALICE, SaleAction::RegisterOnSale, registration_fee_gear);
+    sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    system.spend_blocks(1);
+
+    staking.send(BOB, StakingAction::Unstake(45));
+
+    let result = sale.send(ALICE, SaleAction::CheckStake(BOB.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::StakeChecked(BOB.into(), 5, 0).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetAllocationSizeOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationSize(TOKENS_TO_SELL / 2).encode())));
+}
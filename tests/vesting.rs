@@ -0,0 +1,147 @@
+use ft_io::{FTAction, FTEvent};
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+#[test]
+fn withdraw_allocation_before_cliff_should_withdraw_tge_unlock() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+    let tge_unlocked = tokens_bought * 20 / 100;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let token = system.get_program(SALE_TOKEN_ADDRESS);
+
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        vesting: Some(VestingParameters {
+            tge_unlock_percent: 20,
+            cliff_duration: 100_000_000,
+            vesting_duration: 100_000_000,
+        }),
+        ..sale_parameters(registration_fee_gear)
+    });
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationWithdrawn(ALICE.into(), tge_unlocked).encode())));
+
+    let result = token.send(ALICE, FTAction::BalanceOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, FTEvent::Balance(tge_unlocked).encode())));
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NothingToWithdraw).encode())));
+}
+
+#[test]
+fn withdraw_allocation_after_vesting_should_withdraw_everything_once() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let token = system.get_program(SALE_TOKEN_ADDRESS);
+
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        vesting: Some(VestingParameters {
+            tge_unlock_percent: 10,
+            cliff_duration: 1000,
+            vesting_duration: 1000,
+        }),
+        ..sale_parameters(registration_fee_gear)
+    });
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationWithdrawn(ALICE.into(), tokens_bought).encode())));
+
+    let result = token.send(ALICE, FTAction::BalanceOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, FTEvent::Balance(tokens_bought).encode())));
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::NothingToWithdraw).encode())));
+}
+
+#[test]
+fn withdraw_allocation_during_vesting_should_withdraw_linear_part() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+    let tge_unlocked = tokens_bought * 20 / 100;
+    let vesting_duration = 200_000;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let sale_end_date = system.block_timestamp() + 1 + 80000;
+
+    configure_sale_with_parameters(&system, &sale, SaleParameters {
+        vesting: Some(VestingParameters {
+            tge_unlock_percent: 20,
+            cliff_duration: 0,
+            vesting_duration,
+        }),
+        ..sale_parameters(registration_fee_gear)
+    });
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+
+    // Partway through the linear phase.
+    system.spend_blocks(150);
+
+    let elapsed = (system.block_timestamp() - sale_end_date) as u128;
+    let vested = tge_unlocked + (tokens_bought - tge_unlocked) * elapsed / vesting_duration as u128;
+    assert!(vested > tge_unlocked && vested < tokens_bought);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationWithdrawn(ALICE.into(), vested).encode())));
+
+    system.spend_blocks(200);
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationWithdrawn(ALICE.into(), tokens_bought - vested).encode())));
+}
+
+#[test]
+fn create_sale_with_tge_unlock_above_100_percent_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let sale = system.get_program(SALE_ADDRESS);
+
+    let result = sale.send(SALE_ADMIN, SaleAction::CreateSale(SaleParameters {
+        vesting: Some(VestingParameters {
+            tge_unlock_percent: 101,
+            ..Default::default()
+        }),
+        ..sale_parameters(1000)
+    }));
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::InvalidVesting).encode())));
+}
@@ -0,0 +1,108 @@
+use ed25519_dalek::{Signer, SigningKey};
+use gstd::Encode;
+use gtest::System;
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+fn allocator() -> SigningKey {
+    SigningKey::from_bytes(&[7; 32])
+}
+
+fn sign_voucher(user: u64, max_allocation: u128, expiry: u64, issued_at: u64) -> SignedVoucher {
+    let voucher = AllocationVoucher {
+        sale_id: SALE_ADDRESS.into(),
+        user: user.into(),
+        max_allocation,
+        expiry,
+        issued_at,
+    };
+
+    SignedVoucher {
+        voucher,
+        signature: allocator().sign(&voucher.encode()).to_bytes(),
+    }
+}
+
+fn configure_sale_with_allocator(system: &System, registration_fee_gear: u128) {
+    let sale = system.get_program(SALE_ADDRESS);
+
+    configure_sale_with_parameters(system, &sale, SaleParameters {
+        allocator_key: Some(allocator().verifying_key().to_bytes()),
+        ..sale_parameters(registration_fee_gear)
+    });
+}
+
+#[test]
+fn participate_with_voucher_should_participated() {
+    let system = System::new();
+    init(&system);
+
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    configure_sale_with_allocator(&system, 1000);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let voucher = sign_voucher(ALICE, tokens_bought, system.block_timestamp() + 1000, 1);
+
+    let result = sale.send_with_value(ALICE, SaleAction::ParticipateWithVoucher(voucher), to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought, to_participate, 0).encode())));
+}
+
+#[test]
+fn participate_with_invalid_voucher_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    configure_sale_with_allocator(&system, 1000);
+
+    let sale = system.get_program(SALE_ADDRESS);
+
+    let voucher = sign_voucher(BOB, tokens_bought, system.block_timestamp() + 1000, 1);
+    let result = sale.send_with_value(ALICE, SaleAction::ParticipateWithVoucher(voucher), to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::InvalidVoucher).encode())));
+
+    let mut voucher = sign_voucher(ALICE, tokens_bought, system.block_timestamp() + 1000, 1);
+    voucher.voucher.max_allocation *= 2;
+    let result = sale.send_with_value(ALICE, SaleAction::ParticipateWithVoucher(voucher), to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::InvalidVoucher).encode())));
+
+    let voucher = sign_voucher(ALICE, tokens_bought, system.block_timestamp() - 1, 1);
+    let result = sale.send_with_value(ALICE, SaleAction::ParticipateWithVoucher(voucher), to_participate);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::VoucherExpired).encode())));
+}
+
+#[test]
+fn participate_with_older_voucher_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let to_participate = 5 * 10e18 as u128;
+    let tokens_bought = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, 100 * 10e18 as u128, 100, 50);
+    configure_sale_with_allocator(&system, 1000);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let expiry = system.block_timestamp() + 1000;
+
+    let voucher = sign_voucher(ALICE, tokens_bought / 2, expiry, 2);
+    let result = sale.send_with_value(ALICE, SaleAction::ParticipateWithVoucher(voucher), to_participate / 2);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought / 2, to_participate / 2, 0).encode())));
+
+    let voucher = sign_voucher(ALICE, tokens_bought, expiry, 1);
+    let result = sale.send_with_value(ALICE, SaleAction::ParticipateWithVoucher(voucher), to_participate / 2);
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::VoucherSuperseded).encode())));
+
+    let voucher = sign_voucher(ALICE, tokens_bought, expiry, 3);
+    let result = sale.send_with_value(ALICE, SaleAction::ParticipateWithVoucher(voucher), to_participate / 2);
+    assert!(result.contains(&(ALICE, SaleEvent::TokensPurchased(ALICE.into(), tokens_bought / 2, to_participate / 2, 0).encode())));
+}
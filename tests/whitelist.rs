@@ -0,0 +1,82 @@
+use gstd::{ActorId, Encode};
+use gtest::System;
+use sha2::{Digest, Sha256};
+
+use sale_io::*;
+
+mod shared;
+use shared::*;
+
+fn leaf(who: u64, allocation: Option<u128>) -> [u8; 32] {
+    Sha256::digest((ActorId::from(who), allocation).encode()).into()
+}
+
+fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+
+    Sha256::new().chain_update(left).chain_update(right).finalize().into()
+}
+
+#[test]
+fn register_with_whitelist_proof_should_registered() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+    let allocation = 5 * 10e16 as u128;
+
+    prepare_user_for_registration(&system, ALICE, registration_fee_gear, 100, 50);
+    prepare_user_for_registration(&system, BOB, registration_fee_gear, 100, 50);
+
+    let alice_leaf = leaf(ALICE, Some(allocation));
+    let bob_leaf = leaf(BOB, None);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, SaleParameters {
+        whitelist_root: Some(hash_pair(alice_leaf, bob_leaf)),
+        ..sale_parameters(registration_fee_gear)
+    });
+
+    let result = sale.send_with_value(ALICE, SaleAction::RegisterOnSaleWithProof(WhitelistProof {
+        proof: vec![bob_leaf],
+        allocation: Some(allocation),
+    }), registration_fee_gear);
+    assert!(result.contains(&(ALICE, SaleEvent::UserRegistered(ALICE.into()).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetAllocationSizeOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationSize(allocation).encode())));
+
+    let result = sale.send_with_value(BOB, SaleAction::RegisterOnSaleWithProof(WhitelistProof {
+        proof: vec![alice_leaf],
+        allocation: None,
+    }), registration_fee_gear);
+    assert!(result.contains(&(BOB, SaleEvent::UserRegistered(BOB.into()).encode())));
+}
+
+#[test]
+fn register_with_invalid_whitelist_proof_should_failed() {
+    let system = System::new();
+    init(&system);
+
+    let registration_fee_gear = 1000;
+
+    prepare_user_for_registration(&system, BOB, registration_fee_gear * 2, 100, 50);
+
+    let alice_leaf = leaf(ALICE, Some(100));
+    let bob_leaf = leaf(BOB, None);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    schedule_sale(&system, &sale, SaleParameters {
+        whitelist_root: Some(hash_pair(alice_leaf, bob_leaf)),
+        ..sale_parameters(registration_fee_gear)
+    });
+
+    let result = sale.send_with_value(BOB, SaleAction::RegisterOnSale, registration_fee_gear);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::NotWhitelisted).encode())));
+
+    let result = sale.send_with_value(BOB, SaleAction::RegisterOnSaleWithProof(WhitelistProof {
+        proof: vec![alice_leaf],
+        allocation: Some(100),
+    }), registration_fee_gear);
+    assert!(result.contains(&(BOB, SaleEvent::Error(SaleError::NotWhitelisted).encode())));
+}
@@ -30,7 +30,7 @@ fn widthdraw_registration_fees_when_nothing_to_withdraw_should_failed() {
     system.spend_blocks(5000);
 
     let result = sale.send(SALE_ADMIN, SaleAction::WithdrawRegistrationFees);
-    assert!(result.main_failed());
+    assert!(result.contains(&(SALE_ADMIN, SaleEvent::Error(SaleError::NothingToWithdraw).encode())));
 }
 
 #[test]
@@ -89,7 +89,7 @@ fn widthdraw_earnings_should_withdrawed() {
     let mailbox = system.get_mailbox(SALE_OWNER);
     let log = Log::builder()
         .dest(SALE_OWNER)
-        .payload(SaleEvent::EarningsWithdrawn(to_participate));
+        .payload(SaleEvent::EarningsWithdrawn(vec![(Currency::Gear, to_participate)]));
 
     mailbox.claim_value(log.clone());
 
@@ -152,4 +152,67 @@ fn widthdraw_user_allocation_should_withdrawed() {
 
     let result = token.send(ALICE, FTAction::BalanceOf(ALICE.into()));
     assert!(result.contains(&(ALICE, FTEvent::Balance(tokens_bought).encode())));
-}
\ No newline at end of file
+}
+
+#[test]
+fn claim_allocation_partially_should_claimed() {
+    let system = System::new();
+    init(&system);
+    
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128; 
+    let tokens_bought = 5 * 10e16 as u128; 
+    let to_claim = tokens_bought / 4;
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+    let token = system.get_program(SALE_TOKEN_ADDRESS);
+
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    let result = sale.send(ALICE, SaleAction::ClaimAllocation(to_claim));
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationClaimed(ALICE.into(), to_claim, tokens_bought - to_claim).encode())));
+
+    let result = sale.send(ALICE, SaleAction::GetClaimableOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, SaleEvent::Claimable(tokens_bought - to_claim).encode())));
+
+    let result = sale.send(ALICE, SaleAction::WithdrawAllocation);
+    assert!(result.contains(&(ALICE, SaleEvent::AllocationWithdrawn(ALICE.into(), tokens_bought - to_claim).encode())));
+
+    let result = token.send(ALICE, FTAction::BalanceOf(ALICE.into()));
+    assert!(result.contains(&(ALICE, FTEvent::Balance(tokens_bought).encode())));
+}
+
+#[test]
+fn claim_allocation_above_claimable_should_failed() {
+    let system = System::new();
+    init(&system);
+    
+    let registration_fee_gear = 1000;
+    let plpd_amount = 100;
+    let plpd_to_stake = 50;
+    let total_user_gear_amount = 100 * 10e18 as u128;
+    let to_participate = 5 * 10e18 as u128; 
+    let tokens_bought = 5 * 10e16 as u128; 
+
+    prepare_user_for_registration(&system, ALICE, total_user_gear_amount, plpd_amount, plpd_to_stake);
+
+    let sale = system.get_program(SALE_ADDRESS);
+
+    configure_sale(&system, &sale, registration_fee_gear);
+    set_max_allocation_size_to_user(&system, ALICE, tokens_bought);
+    sale.send_with_value(ALICE, SaleAction::Participate, to_participate);
+    system.spend_blocks(5000);
+
+    sale.send(ALICE, SaleAction::ClaimAllocation(tokens_bought / 2));
+
+    let result = sale.send(ALICE, SaleAction::ClaimAllocation(tokens_bought / 2 + 1));
+    assert!(result.contains(&(ALICE, SaleEvent::Error(SaleError::ClaimExceedsClaimable).encode())));
+}